@@ -0,0 +1,94 @@
+use std::collections::HashSet;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use polyengine::{OccurrenceIndex, Rng};
+
+/// Layout of the occurrence index before `OccurrenceIndex`: one hash set of
+/// variable sets per slot.
+#[derive(Clone)]
+struct HashSetIndex(Vec<HashSet<u128>>);
+
+trait Index: Clone {
+    fn insert_term(&mut self, vars: u128);
+    fn remove_term(&mut self, vars: u128);
+    fn occurrences(&self, var: usize) -> Vec<u128>;
+}
+
+impl Index for HashSetIndex {
+    fn insert_term(&mut self, vars: u128) {
+        for var in (0..128).filter(|v| vars >> v & 1 == 1) {
+            self.0[var].insert(vars);
+        }
+    }
+
+    fn remove_term(&mut self, vars: u128) {
+        for var in (0..128).filter(|v| vars >> v & 1 == 1) {
+            self.0[var].remove(&vars);
+        }
+    }
+
+    fn occurrences(&self, var: usize) -> Vec<u128> {
+        self.0[var].iter().copied().collect()
+    }
+}
+
+impl Index for OccurrenceIndex {
+    fn insert_term(&mut self, vars: u128) {
+        OccurrenceIndex::insert_term(self, vars)
+    }
+
+    fn remove_term(&mut self, vars: u128) {
+        OccurrenceIndex::remove_term(self, vars)
+    }
+
+    fn occurrences(&self, var: usize) -> Vec<u128> {
+        self.iter(var).collect()
+    }
+}
+
+/// 4000 random terms over 48 slots, about 6 variables each.
+fn dense_terms() -> Vec<u128> {
+    let mut rng = Rng::new(1);
+    (0..4000)
+        .map(|_| {
+            let wide = rng.next_u64() as u128 | (rng.next_u64() as u128) << 64;
+            rng.next_u64() as u128 & rng.next_u64() as u128 & wide & ((1 << 48) - 1)
+        })
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// Index updates of substituting slots 0 to 39 one after another by the
+/// product of a fresh slot and the next one, as `and_replace` does.
+fn substitute_all<I: Index>(mut index: I) -> I {
+    for var in 0..40 {
+        let fresh = 1 << (64 + var);
+        for vars in index.occurrences(var) {
+            index.remove_term(vars);
+            index.insert_term(vars & !(1 << var) | fresh | 1 << (var + 1));
+        }
+    }
+    index
+}
+
+fn index_layouts(c: &mut Criterion) {
+    let terms = dense_terms();
+    let mut hash_sets = HashSetIndex(vec![HashSet::new(); 128]);
+    let mut bitmaps = OccurrenceIndex::new();
+    for &vars in &terms {
+        Index::insert_term(&mut hash_sets, vars);
+        Index::insert_term(&mut bitmaps, vars);
+    }
+
+    let mut group = c.benchmark_group("occurrence_index");
+    group.sample_size(10);
+    group.bench_function("hash_sets", |b| {
+        b.iter_batched(|| hash_sets.clone(), substitute_all, BatchSize::LargeInput)
+    });
+    group.bench_function("bitmaps", |b| b.iter_batched(|| bitmaps.clone(), substitute_all, BatchSize::LargeInput));
+    group.finish();
+}
+
+criterion_group!(benches, index_layouts);
+criterion_main!(benches);
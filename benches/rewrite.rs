@@ -0,0 +1,67 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use polyengine::generate::{multiplier, multiplier_engine};
+use polyengine::rewrite::{FixedOrder, MinOccurrence, Rewriter};
+use polyengine::{BPolynom, PolyEngine, Rng};
+
+fn rewrite_multipliers(c: &mut Criterion) {
+    let mut group = c.benchmark_group("multiplier");
+    group.sample_size(10);
+    for width in [4, 8, 10] {
+        let m = multiplier(width);
+        group.bench_with_input(BenchmarkId::new("fixed", width), &m, |b, m| {
+            b.iter_batched(
+                || multiplier_engine(m),
+                |mut en| Rewriter::new(FixedOrder).run(&mut en, &m.gates).unwrap(),
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_with_input(BenchmarkId::new("min_occurrence", width), &m, |b, m| {
+            b.iter_batched(
+                || multiplier_engine(m),
+                |mut en| Rewriter::new(MinOccurrence).run(&mut en, &m.gates).unwrap(),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+/// 4000 random terms over 48 signals, about 6 variables each.
+fn dense_engine() -> PolyEngine {
+    let mut rng = Rng::new(1);
+    let mut p = BPolynom::empty();
+    for _ in 0..4000 {
+        let wide = rng.next_u64() as u128 | (rng.next_u64() as u128) << 64;
+        p.add_term(rng.next_u64() as u128 & rng.next_u64() as u128 & wide & ((1 << 48) - 1), 1 + (rng.next_u64() % 7) as i64);
+    }
+    let mut en = PolyEngine::new(BPolynom::empty());
+    for v in 0..48 {
+        en.assign_var(v, v, format!("s{}", v));
+    }
+    en.add_from_generates(p);
+    en
+}
+
+/// Substitutes highly shared signals, each by the product of a new signal and
+/// the next one.
+fn substitute_dense(c: &mut Criterion) {
+    let en = dense_engine();
+    let mut group = c.benchmark_group("dense");
+    group.sample_size(10);
+    group.bench_function("and_replace", |b| {
+        b.iter_batched(
+            || en.clone(),
+            |mut en| {
+                for v in 0..40 {
+                    en.and_replace(v, 1000 + v, format!("a{}", v), v + 1, format!("s{}", v + 1)).unwrap();
+                }
+                en
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, rewrite_multipliers, substitute_dense);
+criterion_main!(benches);
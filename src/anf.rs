@@ -0,0 +1,115 @@
+//! Algebraic normal form, i.e. a polynomial over GF(2): an XOR of AND
+//! terms. For a Boolean-valued polynomial the ANF consists of exactly the
+//! terms with an odd factor, and the way back expands the XORs with
+//! `x ⊕ y = x + y - 2·x·y`.
+
+use std::collections::BTreeSet;
+use std::fmt::Display;
+use std::ops::Index;
+
+use crate::order::DegLex;
+use crate::{set_bits, BPolynom, MonomialOrder};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Anf {
+    /// Variable sets of the AND terms, `0` being the constant 1.
+    pub terms: BTreeSet<u128>,
+}
+
+impl Anf {
+    pub fn len(&self) -> usize {
+        self.terms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Toggles the AND term with the variables `vars`.
+    pub fn xor_term(&mut self, vars: u128) {
+        if !self.terms.remove(&vars) {
+            self.terms.insert(vars);
+        }
+    }
+
+    /// Value of the ANF if exactly the variables in `assignment` are 1.
+    pub fn eval(&self, assignment: u128) -> bool {
+        self.terms.iter().filter(|&&vars| vars & !assignment == 0).count() % 2 == 1
+    }
+
+    /// Integer polynomial with the same values. It can have up to
+    /// `2^len() - 1` terms and factors up to `2^(len() - 1)`.
+    pub fn to_poly(&self) -> BPolynom {
+        let mut p = BPolynom::empty();
+        for &vars in &self.terms {
+            let mut term = BPolynom::empty();
+            term.add_term(vars, 1);
+            p = &p ^ &term;
+        }
+        p
+    }
+
+    /// Terms joined by ` ⊕ `, highest degree first, `0` if there are none.
+    pub fn to_string<C>(&self, var_names: &C) -> String
+    where
+        C: IntoIterator + Index<usize>,
+        <C as Index<usize>>::Output: Display + Sized,
+    {
+        let mut terms: Vec<u128> = self.terms.iter().copied().collect();
+        terms.sort_by(|&a, &b| DegLex.cmp(b, a));
+        let output: Vec<String> = terms
+            .into_iter()
+            .map(|vars| match vars {
+                0 => String::from("1"),
+                _ => {
+                    let mut names: Vec<String> = set_bits(vars).map(|v| var_names[v].to_string()).collect();
+                    names.reverse();
+                    names.join("·")
+                }
+            })
+            .collect();
+        if output.is_empty() {
+            String::from("0")
+        } else {
+            output.join(" ⊕ ")
+        }
+    }
+}
+
+impl BPolynom {
+    /// ANF of a Boolean-valued polynomial: the terms with an odd factor. For
+    /// other polynomials this is the polynomial modulo 2.
+    pub fn to_anf(&self) -> Anf {
+        Anf { terms: self.poly.iter().filter(|(_, f)| f.get() % 2 != 0).map(|(&vars, _)| vars).collect() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anf_roundtrip() {
+        let (a, b, c) = (BPolynom::from([(1, [0])]), BPolynom::from([(1, [1])]), BPolynom::from([(1, [2])]));
+        let maj = (&a & &b) | (&a & &c) | (&b & &c);
+        let anf = maj.to_anf();
+        assert_eq!(anf.terms, BTreeSet::from([0b011, 0b101, 0b110]));
+        assert_eq!(anf.to_string(&["a", "b", "c"]), "c·b ⊕ c·a ⊕ b·a");
+        assert_eq!(anf.to_poly(), maj);
+
+        let f = !(&a ^ &b) & c.clone();
+        let anf = f.to_anf();
+        assert_eq!(anf.to_string(&["a", "b", "c"]), "c·b ⊕ c·a ⊕ c");
+        assert_eq!(anf.to_poly(), f);
+        for x in 0..8 {
+            assert_eq!(anf.eval(x), f.eval(x) == 1);
+        }
+
+        let mut one = Anf::default();
+        one.xor_term(0);
+        assert_eq!(one.to_poly(), BPolynom::from([(1, [])]));
+        one.xor_term(0);
+        assert_eq!(one.to_string(&["a"]), "0");
+        assert!(one.to_poly().is_empty());
+    }
+}
@@ -0,0 +1,234 @@
+//! Lower and upper bounds of a polynomial over all Boolean assignments.
+//!
+//! Components and splits are described in [`decompose`](crate::decompose).
+//! Once the split budget is used up, a component is bounded by the sums of
+//! its factors and by a roof-duality style linearization, whichever is
+//! tighter.
+
+use crate::decompose::most_frequent_var;
+use crate::table::{value_table, MAX_TABLE_VARS};
+use crate::{set_bits, BPolynom};
+
+/// Limits of the bounds analysis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoundsConfig {
+    /// Components with at most this many variables are enumerated, capped
+    /// at [`MAX_TABLE_VARS`].
+    pub exact_vars: u32,
+    /// Total number of splits on a variable before falling back to
+    /// coefficient sums.
+    pub max_splits: usize,
+}
+
+impl Default for BoundsConfig {
+    fn default() -> Self {
+        BoundsConfig { exact_vars: 16, max_splits: 4096 }
+    }
+}
+
+/// Range containing all values of a polynomial. If `exact` is set, both
+/// ends are attained by some assignment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bounds {
+    pub min: i128,
+    pub max: i128,
+    pub exact: bool,
+}
+
+impl Bounds {
+    /// Bound of the absolute value, e.g. the worst-case error if the
+    /// polynomial is an error term.
+    pub fn max_abs(&self) -> i128 {
+        self.min.abs().max(self.max.abs())
+    }
+}
+
+impl BPolynom {
+    /// Bounds of `p` with the default [`BoundsConfig`].
+    pub fn bounds(&self) -> Bounds {
+        self.bounds_with(&BoundsConfig::default())
+    }
+
+    pub fn bounds_with(&self, config: &BoundsConfig) -> Bounds {
+        let mut splits = 0;
+        bounds_of(self, config, &mut splits)
+    }
+}
+
+fn bounds_of(p: &BPolynom, config: &BoundsConfig, splits: &mut usize) -> Bounds {
+    let constant = p.coeff(0) as i128;
+    let mut total = Bounds { min: constant, max: constant, exact: true };
+    for component in p.components() {
+        let b = component_bounds(&component, config, splits);
+        total.min += b.min;
+        total.max += b.max;
+        total.exact &= b.exact;
+    }
+    total
+}
+
+fn component_bounds(p: &BPolynom, config: &BoundsConfig, splits: &mut usize) -> Bounds {
+    let support = p.support();
+    if support.count_ones() <= config.exact_vars.min(MAX_TABLE_VARS as u32) {
+        return exact_bounds(p, support);
+    }
+    if *splits < config.max_splits {
+        *splits += 1;
+        let (p0, p1) = p.split_on(most_frequent_var(p));
+        let b0 = bounds_of(&p0, config, splits);
+        let b1 = bounds_of(&(p0 + &p1), config, splits);
+        return Bounds { min: b0.min.min(b1.min), max: b0.max.max(b1.max), exact: b0.exact && b1.exact };
+    }
+    let (mut min, mut max) = (0, 0);
+    for factor in p.poly.values() {
+        if factor.get() < 0 {
+            min += factor.get() as i128;
+        } else {
+            max += factor.get() as i128;
+        }
+    }
+    Bounds { min: min.max(-linearized_max(&-p)), max: max.min(linearized_max(p)), exact: false }
+}
+
+/// Upper bound of `p` from a linear function above it. A positive term
+/// `c·x_S` is replaced by `c·Σ w_v·x_v` with weights summing to 1, a negative
+/// one by `λ·c·(Σ x_v - |S| + 1)` with `0 <= λ <= 1`; weights and `λ` are
+/// tuned by subgradient steps. Values are scaled by `ONE` and rounded so that
+/// the bound stays sound.
+fn linearized_max(p: &BPolynom) -> i128 {
+    const ONE: i128 = 1 << 16;
+    const ROUNDS: usize = 64;
+    let mut constant = 0;
+    let mut linear = [0_i128; 128];
+    // variables, factor and weights of each variable summing to `ONE`
+    let mut positive: Vec<(Vec<usize>, i128, Vec<i128>)> = vec![];
+    // variables, factor and `λ`
+    let mut negative: Vec<(Vec<usize>, i128, i128)> = vec![];
+    for m in p.terms() {
+        let vars: Vec<usize> = set_bits(m.var_product).collect();
+        let factor = m.factor.get() as i128;
+        match vars.len() {
+            0 => constant += factor * ONE,
+            1 => linear[vars[0]] += factor * ONE,
+            n if factor > 0 => {
+                let mut weights = vec![ONE / n as i128; n];
+                weights[0] += ONE % n as i128;
+                positive.push((vars, factor, weights));
+            }
+            _ => negative.push((vars, factor, 0)),
+        }
+    }
+
+    let mut best = i128::MAX;
+    let mut step = ONE / 2;
+    for round in 0..ROUNDS {
+        let mut slope = linear;
+        let mut bound = constant;
+        for (vars, factor, weights) in &positive {
+            for (&v, &w) in vars.iter().zip(weights) {
+                slope[v] += factor * w;
+            }
+        }
+        for (vars, factor, lambda) in &negative {
+            for &v in vars {
+                slope[v] += factor * lambda;
+            }
+            bound -= factor * lambda * (vars.len() as i128 - 1);
+        }
+        bound += slope.iter().filter(|&&a| a > 0).sum::<i128>();
+        best = best.min(bound);
+        if positive.is_empty() && negative.is_empty() {
+            break;
+        }
+
+        // move towards relaxations that leave fewer variables set in the
+        // maximizing assignment
+        for (vars, _, lambda) in &mut negative {
+            let set = vars.iter().filter(|&&v| slope[v] > 0).count();
+            if set == vars.len() {
+                *lambda = (*lambda + step).min(ONE);
+            } else if set + 1 < vars.len() {
+                *lambda = (*lambda - step).max(0);
+            }
+        }
+        for (vars, _, weights) in &mut positive {
+            let hi = (0..vars.len()).max_by_key(|&i| slope[vars[i]]).unwrap();
+            let lo = (0..vars.len()).min_by_key(|&i| slope[vars[i]]).unwrap();
+            if slope[vars[hi]] > 0 && slope[vars[lo]] < slope[vars[hi]] {
+                let moved = step.min(weights[hi]);
+                weights[hi] -= moved;
+                weights[lo] += moved;
+            }
+        }
+        if round % 8 == 7 {
+            step = (step / 2).max(1);
+        }
+    }
+    best.div_euclid(ONE)
+}
+
+/// Enumerates all assignments of `support`.
+fn exact_bounds(p: &BPolynom, support: u128) -> Bounds {
+    let values = value_table(p, &set_bits(support).collect::<Vec<_>>());
+    Bounds { min: *values.iter().min().unwrap(), max: *values.iter().max().unwrap(), exact: true }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::random_poly;
+    use crate::Rng;
+
+    fn brute_force(p: &BPolynom, vars: u32) -> (i128, i128) {
+        let values: Vec<i128> = (0..1_u128 << vars).map(|a| p.eval(a)).collect();
+        (*values.iter().min().unwrap(), *values.iter().max().unwrap())
+    }
+
+    #[test]
+    fn exact_and_split_bounds() {
+        let xor = BPolynom::from([(1, vec![0]), (1, vec![1]), (-2, vec![0, 1])]);
+        assert_eq!(xor.bounds(), Bounds { min: 0, max: 1, exact: true });
+
+        let mut rng = Rng::new(7);
+        for _ in 0..20 {
+            let p = random_poly(&mut rng, 12, 0x3ff, 50);
+            let (min, max) = brute_force(&p, 10);
+            assert_eq!(p.bounds(), Bounds { min, max, exact: true });
+            let split = p.bounds_with(&BoundsConfig { exact_vars: 2, max_splits: usize::MAX });
+            assert_eq!(split, Bounds { min, max, exact: true });
+            let relaxed = p.bounds_with(&BoundsConfig { exact_vars: 2, max_splits: 3 });
+            assert!(relaxed.min <= min && relaxed.max >= max);
+        }
+    }
+
+    #[test]
+    fn linearized_bounds() {
+        let relaxed = BoundsConfig { exact_vars: 0, max_splits: 0 };
+        let xor = BPolynom::from([(1, vec![0]), (1, vec![1]), (-2, vec![0, 1])]);
+        assert_eq!(xor.bounds_with(&relaxed), Bounds { min: 0, max: 1, exact: false });
+
+        let mut rng = Rng::new(5);
+        for _ in 0..50 {
+            let p = random_poly(&mut rng, 16, 0x3ff, 50);
+            let (min, max) = brute_force(&p, 10);
+            let b = p.bounds_with(&relaxed);
+            let negative: i128 = p.terms().map(|m| m.factor.get().min(0) as i128).sum();
+            let positive: i128 = p.terms().map(|m| m.factor.get().max(0) as i128).sum();
+            assert!(negative <= b.min && b.min <= min && max <= b.max && b.max <= positive);
+        }
+    }
+
+    #[test]
+    fn components_are_independent() {
+        let p = BPolynom::from([(5, vec![]), (2, vec![0, 1]), (-3, vec![1]), (4, vec![5, 6]), (-1, vec![6]), (1, vec![9])]);
+        assert_eq!(p.components().len(), 3);
+        let b = p.bounds_with(&BoundsConfig { exact_vars: 2, max_splits: 0 });
+        assert_eq!(b, Bounds { min: 5 - 3 - 1, max: 5 + 3 + 1, exact: true });
+        assert_eq!(b.max_abs(), 9);
+
+        // the linearization finds the true maxima 0 and 3 of 2·x0·x1 - 3·x1
+        // and 4·x5·x6 - x6 where the factor sums give 2 and 4
+        let b = p.bounds_with(&BoundsConfig { exact_vars: 1, max_splits: 0 });
+        assert_eq!(b, Bounds { min: 5 - 3 - 1, max: 5 + 3 + 1, exact: false });
+    }
+}
@@ -0,0 +1,241 @@
+//! Model counting: the number of assignments over the support of a
+//! polynomial for which it is non-zero.
+//!
+//! Value distributions are computed per component as described in
+//! [`decompose`](crate::decompose). If a limit is exceeded, the count is
+//! estimated from uniform random samples.
+
+use std::collections::HashMap;
+
+use crate::bounds::BoundsConfig;
+use crate::decompose::most_frequent_var;
+use crate::eval::Lanes;
+use crate::metrics::random_lanes;
+use crate::table::{value_table, MAX_TABLE_VARS};
+use crate::{set_bits, BPolynom, Rng};
+
+/// Limits of model counting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CountConfig {
+    /// Parts with at most this many variables are enumerated, capped at
+    /// [`MAX_TABLE_VARS`].
+    pub exact_vars: u32,
+    /// Total number of splits on a variable before falling back to sampling.
+    pub max_splits: usize,
+    /// Largest number of distinct values tracked for a part before falling
+    /// back to sampling.
+    pub max_values: usize,
+    /// Number of random assignments drawn for an estimate.
+    pub samples: u64,
+    pub seed: u64,
+}
+
+impl Default for CountConfig {
+    fn default() -> Self {
+        CountConfig { exact_vars: 20, max_splits: 4096, max_values: 1 << 16, samples: 1 << 16, seed: 0x5eed }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModelCount {
+    /// Number of variables in the support, assignments are counted over
+    /// these.
+    pub vars: u32,
+    /// Number of assignments with a non-zero value, saturating at
+    /// `u128::MAX` if all 128 slots are used.
+    pub count: u128,
+    /// Number of samples the count was estimated from, `None` if it is
+    /// exact.
+    pub samples: Option<u64>,
+}
+
+impl ModelCount {
+    /// Fraction of the assignments with a non-zero value.
+    pub fn fraction(&self) -> f64 {
+        self.count as f64 / 2_f64.powi(self.vars as i32)
+    }
+}
+
+impl BPolynom {
+    /// Non-zero assignments of `p` with the default [`CountConfig`].
+    pub fn count_nonzero(&self) -> ModelCount {
+        self.count_nonzero_with(&CountConfig::default())
+    }
+
+    pub fn count_nonzero_with(&self, config: &CountConfig) -> ModelCount {
+        let support = self.support();
+        let vars = support.count_ones();
+        let mut splits = 0;
+        if let Some(count) = exact_count(self, config, &mut splits) {
+            return ModelCount { vars, count, samples: None };
+        }
+
+        let mut rng = Rng::new(config.seed);
+        let (mut hits, mut remaining) = (0_u64, config.samples);
+        while remaining > 0 {
+            let mut lanes = [0_u128; 128];
+            for var in set_bits(support) {
+                lanes[var] = random_lanes(&mut rng, 0.5);
+            }
+            let batch = remaining.min(u128::WIDTH as u64);
+            hits += self.eval_lanes(&lanes)[..batch as usize].iter().filter(|&&v| v != 0).count() as u64;
+            remaining -= batch;
+        }
+        let fraction = hits as f64 / config.samples as f64;
+        ModelCount { vars, count: (fraction * 2_f64.powi(vars as i32)).round() as u128, samples: Some(config.samples) }
+    }
+}
+
+/// `count · 2^bits`, saturating.
+fn scale(count: u128, bits: u32) -> u128 {
+    if count == 0 {
+        0
+    } else if bits > count.leading_zeros() {
+        u128::MAX
+    } else {
+        count << bits
+    }
+}
+
+/// Non-zero assignments over the support of `p`, `None` if a limit of
+/// `config` is exceeded. The value distributions of the components are
+/// combined one after another. Partial sums that the remaining components
+/// cannot bring back to zero are counted right away.
+fn exact_count(p: &BPolynom, config: &CountConfig, splits: &mut usize) -> Option<u128> {
+    let components = p.components();
+    let cheap = BoundsConfig { exact_vars: 0, max_splits: 0 };
+    let bounds: Vec<_> = components.iter().map(|c| c.bounds_with(&cheap)).collect();
+    let vars: Vec<u32> = components.iter().map(|c| c.support().count_ones()).collect();
+
+    let mut nonzero = 0_u128;
+    let mut partial = HashMap::from([(p.coeff(0) as i128, 1_u128)]);
+    for (i, component) in components.iter().enumerate() {
+        let (min, max) = bounds[i..].iter().fold((0, 0), |(min, max), b| (min + b.min, max + b.max));
+        let remaining: u32 = vars[i..].iter().sum();
+        partial.retain(|&v, &mut n| {
+            let reachable = v + min <= 0 && v + max >= 0;
+            if !reachable {
+                nonzero = nonzero.saturating_add(scale(n, remaining));
+            }
+            reachable
+        });
+        if partial.is_empty() {
+            break;
+        }
+        partial = convolve(&partial, &component_histogram(component, config, splits)?);
+        if partial.len() > config.max_values {
+            return None;
+        }
+    }
+    Some(partial.into_iter().filter(|&(v, _)| v != 0).fold(nonzero, |acc, (_, n)| acc.saturating_add(n)))
+}
+
+/// Number of assignments over the support of `p` per value.
+fn histogram(p: &BPolynom, config: &CountConfig, splits: &mut usize) -> Option<HashMap<i128, u128>> {
+    let mut values = HashMap::from([(p.coeff(0) as i128, 1_u128)]);
+    for component in p.components() {
+        values = convolve(&values, &component_histogram(&component, config, splits)?);
+        if values.len() > config.max_values {
+            return None;
+        }
+    }
+    Some(values)
+}
+
+fn component_histogram(p: &BPolynom, config: &CountConfig, splits: &mut usize) -> Option<HashMap<i128, u128>> {
+    let support = p.support();
+    let bits = support.count_ones();
+    let mut values = HashMap::new();
+    if bits <= config.exact_vars.min(MAX_TABLE_VARS as u32) {
+        for v in value_table(p, &set_bits(support).collect::<Vec<_>>()) {
+            *values.entry(v).or_default() += 1;
+        }
+        return Some(values);
+    }
+    if *splits >= config.max_splits {
+        return None;
+    }
+    *splits += 1;
+    let (p0, p1) = p.split_on(most_frequent_var(p));
+    let p1 = &p0 + &p1;
+    for part in [p0, p1] {
+        // variables of `p` that dropped out of the cofactor still double
+        // its assignments
+        let missing = bits - 1 - part.support().count_ones();
+        for (v, n) in histogram(&part, config, splits)? {
+            let e = values.entry(v).or_default();
+            *e = scale(n, missing).saturating_add(*e);
+        }
+    }
+    (values.len() <= config.max_values).then_some(values)
+}
+
+/// Distribution of the sum of two independent values.
+fn convolve(a: &HashMap<i128, u128>, b: &HashMap<i128, u128>) -> HashMap<i128, u128> {
+    let mut sum: HashMap<i128, u128> = HashMap::new();
+    for (&x, &n) in a {
+        for (&y, &m) in b {
+            let e = sum.entry(x + y).or_default();
+            *e = e.saturating_add(n.saturating_mul(m));
+        }
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::{broken_multiplier, random_poly};
+
+    #[test]
+    fn exact_counts() {
+        let xor = BPolynom::from([(1, vec![0]), (1, vec![1]), (-2, vec![0, 1])]);
+        assert_eq!(xor.count_nonzero(), ModelCount { vars: 2, count: 2, samples: None });
+        assert_eq!(BPolynom::empty().count_nonzero().count, 0);
+        assert_eq!(BPolynom::from([(3, vec![0, 1]), (1, vec![5])]).count_nonzero().count, 5);
+        let positive = BPolynom::from([(1, vec![]), (1, vec![0]), (2, vec![1, 2]), (1, vec![40])]);
+        assert_eq!(positive.count_nonzero_with(&CountConfig { exact_vars: 0, max_splits: 0, ..Default::default() }).count, 16);
+
+        let mut rng = Rng::new(11);
+        for _ in 0..20 {
+            let p = random_poly(&mut rng, 12, 0x3ff, 4);
+            let support = p.support();
+            let vars: Vec<usize> = set_bits(support).collect();
+            let expected = (0..1_u128 << vars.len())
+                .filter(|&i| p.eval(set_bits(i).fold(0, |acc, j| acc | 1 << vars[j])) != 0)
+                .count() as u128;
+            assert_eq!(p.count_nonzero().count, expected);
+            let split = p.count_nonzero_with(&CountConfig { exact_vars: 2, max_splits: usize::MAX, ..Default::default() });
+            assert_eq!(split, ModelCount { vars: vars.len() as u32, count: expected, samples: None });
+        }
+    }
+
+    #[test]
+    fn scale_saturates_past_the_top_bit() {
+        assert_eq!(scale(1, 127), 1 << 127);
+        assert_eq!(scale(1, 128), u128::MAX);
+        assert_eq!(scale(3, 126), 3 << 126);
+        assert_eq!(scale(3, 127), u128::MAX);
+        assert_eq!(scale(0, 200), 0);
+    }
+
+    #[test]
+    fn count_of_broken_multiplier() {
+        // drop the partial product a1·b1, wrong exactly if a1 = b1 = 1
+        let (_, en) = broken_multiplier(4, &[(1, 1)]);
+        assert_eq!(en.p.count_nonzero().fraction(), 0.25);
+
+        let config = CountConfig { exact_vars: 0, max_splits: 0, samples: 20_000, ..Default::default() };
+        let estimate = en.p.count_nonzero_with(&config);
+        assert_eq!(estimate.samples, Some(20_000));
+        assert_eq!(estimate.count, 1);
+
+        // non-zero unless all 15 products are 0
+        let p = BPolynom::from((0..15).map(|i| (1, vec![2 * i, 2 * i + 1])));
+        let expected = 1.0 - 0.75_f64.powi(15);
+        let exact = p.count_nonzero_with(&CountConfig { exact_vars: 4, ..Default::default() });
+        assert_eq!(exact.samples, None);
+        assert!((exact.fraction() - expected).abs() < 1e-12);
+        assert!((p.count_nonzero_with(&config).fraction() - expected).abs() < 0.01);
+    }
+}
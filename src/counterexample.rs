@@ -0,0 +1,186 @@
+//! Counterexamples: assignments for which a remainder polynomial is
+//! non-zero, fewest set bits first. Only the variables of the remainder are
+//! set.
+
+use std::fmt;
+
+use crate::eval::{pack_lanes, Lanes};
+use crate::{set_bits, BPolynom, PolyEngine};
+
+/// Counterexample rendered as word-level values, see
+/// [`PolyEngine::counterexamples`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counterexample {
+    pub assignment: u128,
+    /// Name and value of each requested word.
+    pub words: Vec<(String, u128)>,
+    pub expected: i128,
+    pub actual: i128,
+}
+
+impl fmt::Display for Counterexample {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (name, value)) in self.words.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}={}", name, value)?;
+        }
+        write!(f, ": expected {}, actual {}", self.expected, self.actual)
+    }
+}
+
+impl BPolynom {
+    /// Up to `n` distinct assignments with a non-zero value, evaluating at
+    /// most 2^20 assignments.
+    pub fn counterexamples(&self, n: usize) -> Vec<u128> {
+        self.counterexamples_with(n, 1 << 20)
+    }
+
+    /// Up to `n` distinct assignments with a non-zero value, fewest set bits
+    /// first, evaluating at most `max_evaluations` assignments.
+    pub fn counterexamples_with(&self, n: usize, max_evaluations: u64) -> Vec<u128> {
+        let support: Vec<usize> = set_bits(self.support()).collect();
+        let m = support.len();
+        let mut found = vec![];
+        let mut batch = vec![];
+        let mut evaluations = 0;
+        let flush = |batch: &mut Vec<u128>, found: &mut Vec<u128>| {
+            let values = self.eval_lanes::<u128>(&pack_lanes(batch));
+            found.extend(batch.drain(..).zip(values).filter(|&(_, v)| v != 0).map(|(a, _)| a));
+        };
+        'weights: for k in 0..=m {
+            // all k-subsets of the support as index masks, in increasing order
+            let mut subset = u128::MAX.checked_shr(128 - k as u32).unwrap_or(0);
+            loop {
+                batch.push(set_bits(subset).fold(0, |acc, i| acc | 1 << support[i]));
+                evaluations += 1;
+                if batch.len() == u128::WIDTH {
+                    flush(&mut batch, &mut found);
+                }
+                if found.len() >= n || evaluations >= max_evaluations {
+                    break 'weights;
+                }
+                match next_subset(subset) {
+                    Some(next) if m == 128 || next >> m == 0 => subset = next,
+                    _ => break,
+                }
+            }
+        }
+        flush(&mut batch, &mut found);
+        found.truncate(n);
+
+        if found.len() < n {
+            for vars in self.minimal_terms() {
+                if found.len() == n {
+                    break;
+                }
+                if !found.contains(&vars) {
+                    found.push(vars);
+                }
+            }
+            found.sort_by_key(|a| a.count_ones());
+        }
+        found
+    }
+
+    /// Variable sets of the terms that contain no other term, fewest
+    /// variables first.
+    fn minimal_terms(&self) -> Vec<u128> {
+        let mut terms: Vec<u128> = self.poly.keys().copied().collect();
+        terms.sort_by_key(|vars| (vars.count_ones(), *vars));
+        let mut minimal: Vec<u128> = vec![];
+        for vars in terms {
+            if minimal.iter().all(|&m| m & !vars != 0) {
+                minimal.push(vars);
+            }
+        }
+        minimal
+    }
+}
+
+/// Next larger mask with the same number of set bits (Gosper's hack).
+fn next_subset(x: u128) -> Option<u128> {
+    if x == 0 {
+        return None;
+    }
+    let c = x & x.wrapping_neg();
+    let r = x.checked_add(c)?;
+    Some((((r ^ x) >> 2) / c) | r)
+}
+
+impl PolyEngine {
+    /// Up to `n` counterexamples of `p` read as the remainder of a
+    /// specification `Z - f(words)`, where the circuit computes
+    /// `f(words) + remainder`. Each word is given by its name and signals,
+    /// least significant bit first; `expected` computes `f` from the word
+    /// values.
+    pub fn counterexamples(
+        &self,
+        words: &[(&str, &[usize])],
+        n: usize,
+        expected: impl Fn(&[u128]) -> i128,
+    ) -> Vec<Counterexample> {
+        self.p
+            .counterexamples(n)
+            .into_iter()
+            .map(|assignment| {
+                let values: Vec<u128> = words
+                    .iter()
+                    .map(|(_, signals)| {
+                        signals.iter().enumerate().fold(0, |acc, (i, signal)| match self.reverse_mapping.get(signal) {
+                            Some(&var) if assignment >> var & 1 == 1 => acc | 1 << i,
+                            _ => acc,
+                        })
+                    })
+                    .collect();
+                let expected = expected(&values);
+                Counterexample {
+                    assignment,
+                    words: words.iter().map(|(name, _)| name.to_string()).zip(values).collect(),
+                    expected,
+                    actual: expected + self.p.eval(assignment),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::broken_multiplier;
+
+    #[test]
+    fn fewest_bits_first() {
+        let p = BPolynom::from([(1, vec![0, 1, 2]), (-1, vec![3, 4]), (1, vec![3, 4, 5])]);
+        let found = p.counterexamples(4);
+        assert_eq!(found[0], 0b11000);
+        assert_eq!(found[1], 0b111);
+        assert_eq!(found.len(), 4);
+        assert!(found.iter().all(|&a| p.eval(a) != 0));
+        assert!(found.windows(2).all(|w| w[0].count_ones() <= w[1].count_ones() && w[0] != w[1]));
+        assert_eq!(p.counterexamples(100).len(), p.count_nonzero().count as usize);
+        assert!(BPolynom::empty().counterexamples(3).is_empty());
+
+        // out of budget, the minimal terms still are counterexamples
+        assert_eq!(p.counterexamples_with(3, 1), [0b11000, 0b111]);
+    }
+
+    #[test]
+    fn word_level_counterexamples() {
+        // drop the partial products a0·b1 and a1·b0
+        let (m, en) = broken_multiplier(3, &[(0, 1), (1, 0)]);
+
+        let found = en.counterexamples(&[("A", &m.a), ("B", &m.b)], 3, |v| (v[0] * v[1]) as i128);
+        assert_eq!(found.len(), 3);
+        let words: Vec<(u128, u128)> = found.iter().map(|c| (c.words[0].1, c.words[1].1)).collect();
+        assert!(words[..2].contains(&(1, 2)) && words[..2].contains(&(2, 1)));
+        assert_eq!(found[0].to_string(), format!("A={}, B={}: expected 2, actual 0", words[0].0, words[0].1));
+        for c in &found {
+            let (a, b) = (c.words[0].1, c.words[1].1);
+            let dropped = (a & 1) * (b >> 1 & 1) + (a >> 1 & 1) * (b & 1);
+            assert_eq!(c.actual, (a * b - 2 * dropped) as i128);
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! Cofactors, the linear split of a polynomial on a single variable and its
+//! independent components.
+//!
+//! Since variables are Boolean, every polynomial is linear in each of them:
+//! `p = x·p1 + p0` where neither `p1` nor `p0` contains `x`. Then the
+//! cofactors are `p|x=0 = p0` and `p|x=1 = p0 + p1`, and the Boolean
+//! difference `p|x=1 - p|x=0` is `p1`.
+//!
+//! Terms that share no variables are independent, so the values of `p` are
+//! the sums of the values of its components and its constant. The bounds
+//! analysis and model counting work on components, enumerate small ones and
+//! split larger ones on their most frequent variable into the cofactors `p0`
+//! and `p0 + p1`, which may decompose further.
+
+use std::collections::HashMap;
+
+use crate::{set_bits, BPolynom};
+
+impl BPolynom {
+    /// `p` with the variable `var` fixed to `value`.
+    pub fn cofactor(&self, var: u32, value: bool) -> BPolynom {
+        let bit = 1_u128 << var;
+        let mut result = BPolynom::empty();
+        for (&vars, factor) in self.poly.iter() {
+            if vars & bit == 0 {
+                result.add_term(vars, factor.get());
+            } else if value {
+                result.add_term(vars & !bit, factor.get());
+            }
+        }
+        result
+    }
+
+    /// Splits `p` into `(p0, p1)` with `p = x·p1 + p0`, `x` being `var`.
+    pub fn split_on(&self, var: u32) -> (BPolynom, BPolynom) {
+        let bit = 1_u128 << var;
+        let (mut p0, mut p1) = (BPolynom::empty(), BPolynom::empty());
+        for (&vars, &factor) in self.poly.iter() {
+            if vars & bit == 0 {
+                p0.poly.insert(vars, factor);
+            } else {
+                p1.poly.insert(vars & !bit, factor);
+            }
+        }
+        (p0, p1)
+    }
+
+    /// Boolean difference `p|x=1 - p|x=0` with respect to `var`. It is zero
+    /// iff `p` does not depend on `var`.
+    pub fn boolean_difference(&self, var: u32) -> BPolynom {
+        self.split_on(var).1
+    }
+
+    /// Splits the non-constant terms into groups that share no variables.
+    pub fn components(&self) -> Vec<BPolynom> {
+        let mut parent: Vec<usize> = (0..128).collect();
+        fn find(parent: &mut [usize], mut v: usize) -> usize {
+            while parent[v] != v {
+                parent[v] = parent[parent[v]];
+                v = parent[v];
+            }
+            v
+        }
+        for &vars in self.poly.keys() {
+            let mut bits = set_bits(vars);
+            if let Some(first) = bits.next() {
+                for v in bits {
+                    let (a, b) = (find(&mut parent, first), find(&mut parent, v));
+                    parent[a] = b;
+                }
+            }
+        }
+        let mut components: HashMap<usize, BPolynom> = HashMap::new();
+        for (&vars, &factor) in self.poly.iter() {
+            if vars != 0 {
+                let root = find(&mut parent, vars.trailing_zeros() as usize);
+                components.entry(root).or_default().poly.insert(vars, factor);
+            }
+        }
+        let mut components: Vec<(usize, BPolynom)> = components.into_iter().collect();
+        components.sort_by_key(|&(root, _)| root);
+        components.into_iter().map(|(_, c)| c).collect()
+    }
+}
+
+/// Variable occurring in the most terms, the variable to split on.
+pub(crate) fn most_frequent_var(p: &BPolynom) -> u32 {
+    let mut counts = [0_usize; 128];
+    for &vars in p.poly.keys() {
+        for v in set_bits(vars) {
+            counts[v] += 1;
+        }
+    }
+    (0..128).max_by_key(|&v| counts[v]).unwrap() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Monom;
+
+    #[test]
+    fn cofactors_and_split() {
+        let p = BPolynom::from([(3, vec![0, 1]), (-2, vec![1]), (5, vec![2]), (1, vec![])]);
+        let x = Monom::from((1, [1]));
+
+        let (p0, p1) = p.split_on(1);
+        assert_eq!(p0, BPolynom::from([(5, vec![2]), (1, vec![])]));
+        assert_eq!(p1, BPolynom::from([(3, vec![0]), (-2, vec![])]));
+        assert_eq!(&p1 * x + &p0, p);
+
+        assert_eq!(p.cofactor(1, false), p0);
+        assert_eq!(p.cofactor(1, true), &p0 + &p1);
+        assert_eq!(p.boolean_difference(1), p.cofactor(1, true) - p.cofactor(1, false));
+        assert!(p.boolean_difference(7).is_empty());
+
+        // terms may merge when the variable is fixed to 1
+        let q = BPolynom::from([(2, vec![0, 1]), (-2, vec![0])]);
+        assert!(q.cofactor(1, true).is_empty());
+        for a in 0..8_u128 {
+            assert_eq!(p.cofactor(2, a & 4 != 0).eval(a), p.eval(a));
+        }
+    }
+}
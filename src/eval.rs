@@ -0,0 +1,150 @@
+//! Evaluation of polynomials on Boolean assignments. An assignment is a
+//! bitset over the variable slots; [`Lanes`] packs 64 or 128 assignments
+//! into one word per slot so that they are evaluated together.
+
+use std::collections::HashMap;
+use std::ops::BitAnd;
+
+use crate::{set_bits, BPolynom, Monom, PolyEngine};
+
+/// Word holding one bit per assignment, see [`BPolynom::eval_lanes`].
+pub trait Lanes: Copy + Eq + BitAnd<Output = Self> {
+    const WIDTH: usize;
+    const ZERO: Self;
+    const ONES: Self;
+    fn lane(self, lane: usize) -> bool;
+    fn with_lane(self, lane: usize) -> Self;
+}
+
+macro_rules! impl_lanes {
+    ($($t:ty),*) => {$(
+        impl Lanes for $t {
+            const WIDTH: usize = <$t>::BITS as usize;
+            const ZERO: Self = 0;
+            const ONES: Self = <$t>::MAX;
+
+            fn lane(self, lane: usize) -> bool {
+                self >> lane & 1 == 1
+            }
+
+            fn with_lane(self, lane: usize) -> Self {
+                self | 1 << lane
+            }
+        }
+    )*};
+}
+
+impl_lanes!(u64, u128);
+
+/// Packs up to `L::WIDTH` assignments, lane `i` holding `assignments[i]`.
+/// Unused lanes are all zero.
+pub fn pack_lanes<L: Lanes>(assignments: &[u128]) -> [L; 128] {
+    assert!(assignments.len() <= L::WIDTH, "more than {} assignments", L::WIDTH);
+    let mut lanes = [L::ZERO; 128];
+    for (i, &assignment) in assignments.iter().enumerate() {
+        for var in set_bits(assignment) {
+            lanes[var] = lanes[var].with_lane(i);
+        }
+    }
+    lanes
+}
+
+impl Monom {
+    /// Value of the monomial if exactly the variables in `assignment` are 1.
+    pub fn eval(&self, assignment: u128) -> i64 {
+        if self.var_product & !assignment == 0 {
+            self.factor.get()
+        } else {
+            0
+        }
+    }
+
+    /// Lanes in which all variables of the monomial are 1.
+    pub fn eval_lanes<L: Lanes>(&self, lanes: &[L; 128]) -> L {
+        set_bits(self.var_product).fold(L::ONES, |acc, var| acc & lanes[var])
+    }
+}
+
+impl BPolynom {
+    /// Value of the polynomial if exactly the variables in `assignment` are 1.
+    pub fn eval(&self, assignment: u128) -> i128 {
+        self.terms().map(|m| m.eval(assignment) as i128).sum()
+    }
+
+    /// Values of the polynomial for all lanes of `lanes` at once.
+    pub fn eval_lanes<L: Lanes>(&self, lanes: &[L; 128]) -> Vec<i128> {
+        let mut values = vec![0; L::WIDTH];
+        for m in self.terms() {
+            let hits = m.eval_lanes(lanes);
+            if hits == L::ZERO {
+                continue;
+            }
+            for (lane, value) in values.iter_mut().enumerate() {
+                if hits.lane(lane) {
+                    *value += m.factor.get() as i128;
+                }
+            }
+        }
+        values
+    }
+}
+
+impl PolyEngine {
+    /// Assignment of the slots bound to the signals set in `values`. Signals
+    /// that are missing or not part of `p` are ignored.
+    pub fn assignment(&self, values: &HashMap<usize, bool>) -> u128 {
+        values
+            .iter()
+            .filter(|&(_, &value)| value)
+            .filter_map(|(signal, _)| self.reverse_mapping.get(signal))
+            .fold(0, |acc, &var| acc | 1 << var)
+    }
+
+    /// Value of `p` for the given signal values, missing signals count as 0.
+    pub fn eval(&self, values: &HashMap<usize, bool>) -> i128 {
+        self.p.eval(self.assignment(values))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::{multiplier, multiplier_engine};
+
+    #[test]
+    fn eval_multiplier_spec() {
+        let m = multiplier(3);
+        let en = multiplier_engine(&m);
+        let word = |signals: &[usize], value: u64, values: &mut HashMap<usize, bool>| {
+            for (i, &s) in signals.iter().enumerate() {
+                values.insert(s, value >> i & 1 == 1);
+            }
+        };
+        for (a, b) in [(0, 0), (3, 5), (7, 7), (6, 1)] {
+            let mut values = HashMap::new();
+            word(&m.a, a, &mut values);
+            word(&m.b, b, &mut values);
+            word(&m.z, a * b, &mut values);
+            assert_eq!(en.eval(&values), 0);
+            word(&m.z, a * b + 1, &mut values);
+            assert_eq!(en.eval(&values), 1);
+        }
+    }
+
+    #[test]
+    fn lanes_match_scalar_eval() {
+        let p = BPolynom::from([(3, vec![0, 1]), (-2, vec![2]), (5, vec![]), (7, vec![1, 2, 127])]);
+        assert_eq!(Monom::from((3, [0, 1])).eval(0b111), 3);
+        assert_eq!(Monom::from((3, [0, 1])).eval(0b101), 0);
+
+        let assignments: Vec<u128> = (0..100).map(|i: u128| (i * 0x9e37_79b9) ^ ((i & 1) << 127)).collect();
+        let values = p.eval_lanes::<u128>(&pack_lanes(&assignments));
+        for (i, &a) in assignments.iter().enumerate() {
+            assert_eq!(values[i], p.eval(a));
+        }
+        assert_eq!(values[100..], [5; 28]);
+
+        let values = p.eval_lanes::<u64>(&pack_lanes(&assignments[..64]));
+        assert_eq!(values, assignments[..64].iter().map(|&a| p.eval(a)).collect::<Vec<_>>());
+    }
+}
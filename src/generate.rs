@@ -0,0 +1,101 @@
+//! Generators for benchmark and test circuits.
+
+use crate::rewrite::Gate;
+#[cfg(test)]
+use crate::rewrite::{FixedOrder, Rewriter};
+#[cfg(test)]
+use crate::Rng;
+use crate::{BPolynom, PolyEngine};
+
+/// Gate list of a multiplier together with the signals of its words, least
+/// significant bit first.
+pub struct Multiplier {
+    pub gates: Vec<Gate>,
+    pub a: Vec<usize>,
+    pub b: Vec<usize>,
+    pub z: Vec<usize>,
+}
+
+/// Unsigned array multiplier built from AND partial products that are
+/// reduced column by column with half and full adders.
+pub fn multiplier(width: usize) -> Multiplier {
+    let mut next = 1000;
+    let mut signal = || {
+        next += 1;
+        next
+    };
+    let a: Vec<usize> = (0..width).map(|_| signal()).collect();
+    let b: Vec<usize> = (0..width).map(|_| signal()).collect();
+    let mut gates = vec![];
+    let name = |s: usize| format!("n{}", s);
+    let mut columns: Vec<Vec<usize>> = vec![vec![]; 2 * width];
+    for i in 0..width {
+        for j in 0..width {
+            let pp = signal();
+            gates.push(Gate::and(pp, a[i], name(a[i]), b[j], name(b[j])));
+            columns[i + j].push(pp);
+        }
+    }
+    let mut z = vec![];
+    for k in 0..2 * width {
+        let mut column = std::mem::take(&mut columns[k]);
+        while column.len() > 1 {
+            let x = column.pop().unwrap();
+            let y = column.pop().unwrap();
+            let (sum, carry) = (signal(), signal());
+            if let Some(c) = column.pop() {
+                let (t, g0, g1) = (signal(), signal(), signal());
+                gates.push(Gate::xor(t, x, name(x), y, name(y)));
+                gates.push(Gate::xor(sum, t, name(t), c, name(c)));
+                gates.push(Gate::and(g0, x, name(x), y, name(y)));
+                gates.push(Gate::and(g1, t, name(t), c, name(c)));
+                gates.push(Gate::or(carry, g0, name(g0), g1, name(g1)));
+            } else {
+                gates.push(Gate::xor(sum, x, name(x), y, name(y)));
+                gates.push(Gate::and(carry, x, name(x), y, name(y)));
+            }
+            column.insert(0, sum);
+            if k + 1 < 2 * width {
+                columns[k + 1].push(carry);
+            }
+        }
+        z.extend(column.pop());
+    }
+    Multiplier { gates, a, b, z }
+}
+
+/// Engine holding `Z - A·B` for the given multiplier.
+pub fn multiplier_engine(m: &Multiplier) -> PolyEngine {
+    let mut en = PolyEngine::new(BPolynom::empty());
+    let names = |v: &Vec<usize>| v.iter().map(|s| format!("n{}", s)).collect();
+    let z = en.get_unsigned_poly(m.z.clone(), names(&m.z));
+    let a = en.get_unsigned_poly(m.a.clone(), names(&m.a));
+    let b = en.get_unsigned_poly(m.b.clone(), names(&m.b));
+    en.add_from_generates(z + &((&a * &b) * -1));
+    en
+}
+
+/// Multiplier with the partial products `a_i·b_j` for `(i, j)` in `dropped`
+/// replaced by 0, rewritten to its remainder.
+#[cfg(test)]
+pub(crate) fn broken_multiplier(width: usize, dropped: &[(usize, usize)]) -> (Multiplier, PolyEngine) {
+    let mut m = multiplier(width);
+    for &(i, j) in dropped {
+        let gate = i * width + j;
+        m.gates[gate] = Gate::const_0(m.gates[gate].out);
+    }
+    let mut en = multiplier_engine(&m);
+    Rewriter::new(FixedOrder).run(&mut en, &m.gates).unwrap();
+    (m, en)
+}
+
+/// Sum of `terms` random terms over the variables in `vars`, with factors
+/// below `max_factor` in absolute value.
+#[cfg(test)]
+pub(crate) fn random_poly(rng: &mut Rng, terms: usize, vars: u128, max_factor: i64) -> BPolynom {
+    let mut p = BPolynom::empty();
+    for _ in 0..terms {
+        p.add_term(rng.next_u64() as u128 & rng.next_u64() as u128 & vars, rng.next_u64() as i64 % max_factor);
+    }
+    p
+}
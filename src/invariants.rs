@@ -0,0 +1,125 @@
+use std::fmt::Display;
+
+use crate::{Monom, PolyEngine};
+
+/// First inconsistency found by [`PolyEngine::check_invariants`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvariantViolation {
+    /// A term of `p` is missing from the occurrence set of one of its
+    /// variables.
+    MissingOccurrence { var: usize, monom: Monom },
+    /// An occurrence set holds a variable set that is not a term of `p` or
+    /// does not contain the variable.
+    StaleOccurrence { var: usize, var_product: u128 },
+    /// A variable of `p` sits in a slot marked as free.
+    VariableInFreeSlot { var: usize },
+    /// `reverse_mapping` maps `signal` to `var`, but the slot is free or
+    /// mapped to another signal.
+    MappingMismatch { signal: usize, var: usize },
+}
+
+impl Display for InvariantViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvariantViolation::MissingOccurrence { var, monom } => {
+                write!(f, "term {:?} is missing from the occurrences of variable {}", monom, var)
+            }
+            InvariantViolation::StaleOccurrence { var, var_product } => {
+                write!(f, "occurrences of variable {} hold stale monomial {:x}", var, var_product)
+            }
+            InvariantViolation::VariableInFreeSlot { var } => write!(f, "variable {} is used but its slot is free", var),
+            InvariantViolation::MappingMismatch { signal, var } => {
+                write!(f, "signal {} maps to slot {}, which is not bound to it", signal, var)
+            }
+        }
+    }
+}
+
+impl std::error::Error for InvariantViolation {}
+
+impl PolyEngine {
+    /// Validates the occurrence sets and slot tables against `p`.
+    pub fn check_invariants(&self) -> Result<(), InvariantViolation> {
+        for m in self.p.terms() {
+            for var in m.all_used_vars() {
+                if self.free_var_slots[var] {
+                    return Err(InvariantViolation::VariableInFreeSlot { var });
+                }
+                if !self.var_occurences.contains(var, m.var_product) {
+                    return Err(InvariantViolation::MissingOccurrence { var, monom: m });
+                }
+            }
+        }
+        for var in 0..128 {
+            for var_product in self.var_occurences.iter(var) {
+                if var_product & (1 << var) == 0 || !self.p.poly.contains_key(&var_product) {
+                    return Err(InvariantViolation::StaleOccurrence { var, var_product });
+                }
+            }
+        }
+        for (&signal, &var) in self.reverse_mapping.iter() {
+            let var = var as usize;
+            if var >= 128 || self.free_var_slots[var] || self.var_mapping[var] != signal {
+                return Err(InvariantViolation::MappingMismatch { signal, var });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::{multiplier, multiplier_engine};
+    use crate::rewrite::{AbortReason, FixedOrder, MinOccurrence, Rewriter};
+    use crate::EngineError;
+
+    #[test]
+    fn invariants_hold_during_rewriting() {
+        for width in 2..=4 {
+            let m = multiplier(width);
+            let mut en = multiplier_engine(&m);
+            en.debug_invariants = true;
+            assert_eq!(en.check_invariants(), Ok(()));
+            Rewriter::new(MinOccurrence).run(&mut en, &m.gates).unwrap();
+            assert_eq!(en.check_invariants(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn detect_violations() {
+        let m = multiplier(2);
+        let mut en = multiplier_engine(&m);
+        let t = en.p.terms().find(|t| t.degree() == 2).unwrap();
+        let var = t.all_used_vars()[0];
+
+        en.var_occurences.remove(var, t.var_product);
+        assert_eq!(en.check_invariants(), Err(InvariantViolation::MissingOccurrence { var, monom: t }));
+        en.var_occurences.insert(var, t.var_product);
+        assert_eq!(en.check_invariants(), Ok(()));
+
+        let stale = Monom::from((1, [var as u32, 127])).var_product;
+        en.var_occurences.insert(var, stale);
+        assert_eq!(en.check_invariants(), Err(InvariantViolation::StaleOccurrence { var, var_product: stale }));
+        en.var_occurences.remove(var, stale);
+
+        en.free_var_slots[var] = true;
+        assert_eq!(en.check_invariants(), Err(InvariantViolation::VariableInFreeSlot { var }));
+        en.free_var_slots[var] = false;
+
+        en.var_mapping[var] += 1;
+        assert!(matches!(en.check_invariants(), Err(InvariantViolation::MappingMismatch { .. })));
+    }
+
+    #[test]
+    fn debug_mode_reports_gate() {
+        let m = multiplier(2);
+        let mut en = multiplier_engine(&m);
+        en.debug_invariants = true;
+        let t = en.p.terms().next().unwrap();
+        en.var_occurences.take(t.all_used_vars()[0]);
+        let abort = Rewriter::new(FixedOrder).run(&mut en, &m.gates).unwrap_err();
+        assert!(matches!(abort.reason, AbortReason::Engine(EngineError::InvariantViolated(_))));
+        assert_eq!(abort.gate, *Rewriter::new(FixedOrder).run(&mut multiplier_engine(&m), &m.gates).unwrap().order.first().unwrap());
+    }
+}
@@ -1,11 +1,65 @@
+use std::collections::hash_map::Entry;
 use std::collections::{HashSet, HashMap};
 use std::fmt::Display;
-use std::hash::Hash;
 use std::num::{NonZeroI64};
 use std::ops::{Add, Mul, Sub, Index, AddAssign};
 use array_init::array_init;
+use rewrite::GateKind;
+
+pub mod anf;
+pub mod bounds;
+pub mod budget;
+pub mod count;
+pub mod counterexample;
+pub mod decompose;
+pub mod eval;
+pub mod generate;
+pub mod invariants;
+pub mod metrics;
+pub mod occurrences;
+pub mod order;
+pub mod parse;
+pub mod rewrite;
+pub mod rng;
+pub mod snapshot;
+pub mod table;
+pub mod undo;
+mod ops;
+mod self_check;
+#[cfg(feature = "serde")]
+mod serde_impls;
+
+pub use anf::Anf;
+pub use bounds::{Bounds, BoundsConfig};
+pub use budget::{Budget, CancelToken};
+pub use count::{CountConfig, ModelCount};
+pub use counterexample::Counterexample;
+pub use eval::Lanes;
+pub use invariants::InvariantViolation;
+pub use metrics::{Distribution, ErrorMetrics, MetricsConfig};
+pub use occurrences::OccurrenceIndex;
+pub use order::MonomialOrder;
+pub use parse::ParseError;
+pub use rng::Rng;
+pub use self_check::SelfCheckFailure;
+pub use table::TableError;
+pub use undo::CheckpointId;
+
+/// Indices of the set bits of `bits`, lowest first.
+pub(crate) fn set_bits(mut bits: u128) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        if bits == 0 {
+            return None;
+        }
+        let index = bits.trailing_zeros() as usize;
+        bits &= bits - 1;
+        Some(index)
+    })
+}
 
-#[derive(Clone, Copy, Debug)]
+/// Term of a polynomial. Monomials compare by their variable set first and
+/// by their factor second.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Monom {
     var_product: u128,
     factor: NonZeroI64,
@@ -58,7 +112,7 @@ impl Mul<Monom> for Monom {
 
     fn mul(mut self, rhs: Monom) -> Self::Output {
         self.factor = NonZeroI64::new(self.factor.get() * rhs.factor.get()).unwrap();
-        self.var_product = self.var_product | rhs.var_product;
+        self.var_product |= rhs.var_product;
         self
     }
 }
@@ -87,35 +141,6 @@ impl<C: IntoIterator<Item = u32>> From<(i64, C)> for Monom {
     }
 }
 
-impl PartialEq for Monom {
-    fn eq(&self, other: &Self) -> bool {
-        self.var_product == other.var_product
-        
-    }
-}
-
-impl Eq for Monom {
-}
-
-impl Hash for Monom {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        self.var_product.hash(state);
-    }
-}
-
-impl PartialOrd for Monom {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.factor.get().abs().cmp(&other.factor.get().abs()))
-    }
-}
-
-impl Ord for Monom {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.factor.get().abs().cmp(&other.factor.get().abs())
-    }
-}
-
-
 impl Monom {
     pub fn all_used_vars(&self) -> Vec<usize> {
         let mut used = vec![];
@@ -142,28 +167,11 @@ impl Monom {
         used
     }
 
-    pub fn all_free_vars(&self) -> ([bool; 128]) {
+    pub fn all_free_vars(&self) -> [bool; 128] {
         let mut free = [false; 128];
-        let mut vars = self.var_product;
-        let mut index = u128::BITS as usize;
-        loop {
-            if vars == u128::MAX {
-                break;
-            }
-            index -= 1;
-            let clo = vars.leading_ones();
-            index -= clo as usize;
-
-            free[index] = true;
-            (vars, _) = vars.overflowing_shl(clo + 1);
-
-
-            if index < 1 {
-                break;
-            }
-
+        for (index, f) in free.iter_mut().enumerate() {
+            *f = self.var_product & (1 << index) == 0;
         }
-
         free
     }
 
@@ -212,26 +220,35 @@ impl Monom {
     pub fn set_var(&mut self, var: u32) {
         self.var_product |= 1_u128 << var;
     }
+
+    pub fn degree(&self) -> u32 {
+        self.var_product.count_ones()
+    }
+
+    /// Number of bits needed for the absolute value of the factor.
+    pub fn coeff_bits(&self) -> u32 {
+        u64::BITS - self.factor.get().unsigned_abs().leading_zeros()
+    }
 }
 
-#[derive(Debug, Clone)]
+/// Sum of monomials, stored as a map from the variable set of each term to
+/// its non-zero factor.
+#[derive(Clone, Default, PartialEq, Eq)]
 pub struct BPolynom {
-    pub poly: HashSet<Monom>,
+    pub poly: HashMap<u128, NonZeroI64>,
+}
+
+impl std::fmt::Debug for BPolynom {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.canonical_terms()).finish()
+    }
 }
 
 impl Add<Monom> for BPolynom {
     type Output = BPolynom;
 
     fn add(mut self, rhs: Monom) -> Self::Output {
-        if let Some(&m) = self.poly.get(&rhs) {
-            let result = m + rhs.factor.get();
-            self.poly.remove(&rhs);
-            if let Some(m) = result {
-                self.poly.insert(m);
-            }
-        } else {
-            self.poly.insert(rhs);
-        }
+        self.add_term(rhs.var_product, rhs.factor.get());
         self
     }
 }
@@ -240,42 +257,14 @@ impl Add<&Monom> for &mut BPolynom {
     type Output = ();
 
     fn add(self, rhs: &Monom) -> Self::Output {
-        if let Some(&m) = self.poly.get(rhs) {
-            let result = m + rhs.factor.get();
-            self.poly.remove(&rhs);
-            if let Some(m) = result {
-                self.poly.insert(m);
-            }
-        } else {
-            self.poly.insert(*rhs);
-        }
-        ()
+        self.add_term(rhs.var_product, rhs.factor.get());
     }
 }
 
 impl AddAssign<&Monom> for BPolynom {
 
     fn add_assign(&mut self, rhs: &Monom) {
-        if let Some(&m) = self.poly.get(rhs) {
-            let result = m + rhs.factor.get();
-            self.poly.remove(&rhs);
-            if let Some(m) = result {
-                self.poly.insert(m);
-            }
-        } else {
-            self.poly.insert(*rhs);
-        }
-    }
-}
-
-impl Add<&BPolynom> for BPolynom {
-    type Output = Self;
-
-    fn add(mut self, rhs: &BPolynom) -> Self::Output {
-        for m in rhs.poly.iter() {
-            self = self + *m;
-        }
-        self
+        self.add_term(rhs.var_product, rhs.factor.get());
     }
 }
 
@@ -283,13 +272,11 @@ impl Mul<i64> for BPolynom {
     type Output = BPolynom;
 
     fn mul(mut self, rhs: i64) -> Self::Output {
-        let vec_monoms: Vec<Monom> = self.poly.iter().map(|x| *x).collect();
-        for m in vec_monoms {
-            self.poly.remove(&m);
-            let v = m * rhs;
-            if let Some(m1) = v {
-                self.poly.insert(m1);
-            }
+        if rhs == 0 {
+            self.poly.clear();
+        }
+        for factor in self.poly.values_mut() {
+            *factor = NonZeroI64::new(factor.get() * rhs).unwrap();
         }
         self
     }
@@ -299,14 +286,12 @@ impl Mul<i64> for BPolynom {
 impl Mul<Monom> for BPolynom {
     type Output = BPolynom;
 
-    fn mul(mut self, rhs: Monom) -> Self::Output {
-        let vec_monoms: Vec<Monom> = self.poly.iter().map(|x| *x).collect();
-        for m in vec_monoms {
-            self.poly.remove(&m);
-            let v = m * rhs;
-            self.poly.insert(v);
+    fn mul(self, rhs: Monom) -> Self::Output {
+        let mut product = BPolynom::empty();
+        for (var_product, factor) in self.poly {
+            product.add_term(var_product | rhs.var_product, factor.get() * rhs.factor.get());
         }
-        self
+        product
     }
 }
 
@@ -314,12 +299,7 @@ impl Mul<&BPolynom> for &BPolynom {
     type Output = BPolynom;
 
     fn mul(self, rhs: &BPolynom) -> Self::Output {
-        let mut product_poly = BPolynom::empty();
-        for &m in rhs.poly.iter() {
-            let partial = self.clone() * m;
-            product_poly = product_poly + &partial;
-        }
-        product_poly
+        self.mul_with_budget(rhs, &Budget::unlimited()).unwrap()
     }
 }
 
@@ -329,9 +309,7 @@ where
     I: IntoIterator<Item = u32>,
 {
     fn from(container: O) -> Self {
-        let mut new_poly = BPolynom {
-            poly: HashSet::new(),
-        };
+        let mut new_poly = BPolynom::empty();
         for tuple in container {
             let m = Monom::from(tuple);
             new_poly = new_poly + m;
@@ -342,22 +320,112 @@ where
 
 impl BPolynom {
     pub fn empty() -> Self {
-        BPolynom { poly: HashSet::new() }
+        BPolynom { poly: HashMap::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.poly.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.poly.is_empty()
+    }
+
+    /// All terms in arbitrary order.
+    pub fn terms(&self) -> impl Iterator<Item = Monom> + '_ {
+        self.poly.iter().map(|(&var_product, &factor)| Monom { var_product, factor })
+    }
+
+    /// Term with exactly the variables `var_product`, if any.
+    pub fn get_term(&self, var_product: u128) -> Option<Monom> {
+        self.poly.get(&var_product).map(|&factor| Monom { var_product, factor })
+    }
+
+    /// Factor of the term with the variables `var_product`, 0 if there is none.
+    pub fn coeff(&self, var_product: u128) -> i64 {
+        self.poly.get(&var_product).map_or(0, |f| f.get())
+    }
+
+    /// Entry of the term with the variables `var_product` for in-place
+    /// updates. Factors must stay non-zero, remove the entry instead.
+    pub fn entry(&mut self, var_product: u128) -> Entry<'_, u128, NonZeroI64> {
+        self.poly.entry(var_product)
+    }
+
+    /// Adds `delta` to the factor of the term with the variables
+    /// `var_product`, dropping the term if it cancels out, and returns the
+    /// previous factor.
+    pub fn add_term(&mut self, var_product: u128, delta: i64) -> Option<NonZeroI64> {
+        match self.poly.entry(var_product) {
+            Entry::Occupied(mut e) => {
+                let previous = *e.get();
+                match NonZeroI64::new(previous.get() + delta) {
+                    Some(factor) => {
+                        e.insert(factor);
+                    }
+                    None => {
+                        e.remove();
+                    }
+                }
+                Some(previous)
+            }
+            Entry::Vacant(e) => {
+                if let Some(factor) = NonZeroI64::new(delta) {
+                    e.insert(factor);
+                }
+                None
+            }
+        }
+    }
+
+    pub fn degree(&self) -> u32 {
+        self.terms().map(|m| m.degree()).max().unwrap_or(0)
+    }
+
+    /// Set of all variables occurring in some term.
+    pub fn support(&self) -> u128 {
+        self.poly.keys().fold(0, |acc, vars| acc | vars)
+    }
+
+    pub fn max_coeff_bits(&self) -> u32 {
+        self.terms().map(|m| m.coeff_bits()).max().unwrap_or(0)
+    }
+
+    /// Same as `&self * rhs`, but polls `budget` after every partial product.
+    pub fn mul_with_budget(&self, rhs: &BPolynom, budget: &Budget) -> Result<BPolynom, EngineError> {
+        let mut product_poly = BPolynom::empty();
+        for (&rhs_vars, rhs_factor) in rhs.poly.iter() {
+            budget.check()?;
+            for (&vars, factor) in self.poly.iter() {
+                product_poly.add_term(vars | rhs_vars, factor.get() * rhs_factor.get());
+            }
+        }
+        Ok(product_poly)
     }
 
+    /// Terms in the canonical [`order::DegLex`] order, separated by
+    /// `seperator`.
     pub fn to_string<C>(&self, var_names: &C, seperator: &str) -> String 
     where
         C: IntoIterator + Index<usize>,
         <C as Index<usize>>::Output: Display + Sized,
+    {
+        self.to_string_by(var_names, seperator, order::DegLex)
+    }
+
+    /// Like `to_string`, but lists the terms by `order`.
+    pub fn to_string_by<C, O>(&self, var_names: &C, seperator: &str, order: O) -> String
+    where
+        C: IntoIterator + Index<usize>,
+        <C as Index<usize>>::Output: Display + Sized,
+        O: MonomialOrder,
     {
         let mut output = String::from("");
-        let mut sorted_vec: Vec<Monom> = self.poly.iter().map(|x|*x).collect();
-        sorted_vec.sort_by(|a, b| b.factor.get().abs().cmp(&a.factor.get().abs()));
-        for m in sorted_vec {
+        for m in self.sorted_terms(order) {
             output.push_str(&m.to_string(var_names));
             output.push_str(seperator);
         }
-        if output.len() > 0 {
+        if !output.is_empty() {
             output.truncate(output.len() - seperator.len());
         } else {
             output = String::from("0");
@@ -366,14 +434,57 @@ impl BPolynom {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EngineError {
+    Cancelled,
+    DeadlineExceeded,
+    InvariantViolated(Box<InvariantViolation>),
+    SelfCheckFailed(Box<SelfCheckFailure>),
+    /// The signal is not bound to a variable of `p`.
+    UnknownSignal(usize),
+    /// All 128 variable slots are in use.
+    NoFreeSlot,
+}
+
+impl Display for EngineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EngineError::Cancelled => write!(f, "operation was cancelled"),
+            EngineError::DeadlineExceeded => write!(f, "deadline exceeded"),
+            EngineError::InvariantViolated(v) => write!(f, "invariant violated: {}", v),
+            EngineError::SelfCheckFailed(failure) => write!(f, "self-check failed: {}", failure),
+            EngineError::UnknownSignal(signal) => write!(f, "signal {} is not part of the polynomial", signal),
+            EngineError::NoFreeSlot => write!(f, "no free variable slot"),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {}
+
 #[derive(Debug, Clone)]
 pub struct PolyEngine {
     pub p: BPolynom,
     pub var_names: [String; 128],
     pub var_mapping: [usize; 128],
     pub reverse_mapping: HashMap<usize, u32>,
-    pub var_occurences: [HashSet<Monom>; 128],
+    /// Variable sets of all terms of `p` containing the respective variable.
+    pub var_occurences: OccurrenceIndex,
     pub free_var_slots: [bool; 128],
+    /// Polled before every replacement, a replacement that runs out of budget
+    /// leaves the engine untouched.
+    pub budget: Budget,
+    /// Runs `check_invariants` after every replacement.
+    pub debug_invariants: bool,
+    /// Compares `p` before and after every replacement on 128 random input
+    /// vectors in which the output variable follows the substituted gate.
+    pub self_check: bool,
+    /// Prints every replacement to stdout.
+    pub trace: bool,
+    rng: Rng,
+    undo_log: Vec<undo::Undo>,
+    /// Active checkpoints with the length of `undo_log` when each was taken.
+    checkpoints: Vec<(CheckpointId, usize)>,
+    next_checkpoint: usize,
 }
 
 impl PolyEngine {
@@ -383,15 +494,21 @@ impl PolyEngine {
             var_names: array_init(|_| String::new()),
             var_mapping: [0; 128],
             reverse_mapping: HashMap::new(),
-            var_occurences: array_init(|_| HashSet::new()),
+            var_occurences: OccurrenceIndex::new(),
             free_var_slots: [true; 128],
+            budget: Budget::unlimited(),
+            debug_invariants: false,
+            self_check: false,
+            trace: false,
+            rng: Rng::default(),
+            undo_log: vec![],
+            checkpoints: vec![],
+            next_checkpoint: 0,
         };
         let mut bitset = 0;
-        for m in new_engine.p.poly.iter() {
+        for m in new_engine.p.terms() {
             bitset |= m.var_product;
-            for index in m.all_used_vars() {
-                new_engine.var_occurences[index].insert(*m);
-            }
+            new_engine.var_occurences.insert_term(m.var_product);
         }
         new_engine.free_var_slots = (Monom{ factor: NonZeroI64::new(1).unwrap(), var_product: bitset }).all_free_vars();
 
@@ -399,27 +516,50 @@ impl PolyEngine {
     }
 
     pub fn add_from_generates(&mut self, poly: BPolynom) {
+        let touched: Vec<u128> = self.p.poly.keys().chain(poly.poly.keys()).copied().collect();
+        for vars in touched {
+            self.save_term(vars, self.p.poly.get(&vars).copied());
+        }
+        self.var_occurences.clear();
         self.p = poly;
-        let mut bitset = 0;
-        for m in self.p.poly.iter() {
-            bitset |= m.var_product;
-            for index in m.all_used_vars() {
-                self.var_occurences[index].insert(*m);
-            }
+        for m in self.p.terms() {
+            self.var_occurences.insert_term(m.var_product);
         }
     } 
 
     pub fn next_free_var(&self) -> Option<usize> {
-        self.free_var_slots.iter().position(|&x| x == true)
+        self.free_var_slots.iter().position(|&x| x)
+    }
+
+    /// Binds the free slot `var` to `signal`.
+    pub fn assign_var(&mut self, var: usize, signal: usize, name: String) {
+        self.save_slot(var);
+        self.save_reverse(signal);
+        self.free_var_slots[var] = false;
+        self.var_names[var] = name;
+        self.var_mapping[var] = signal;
+        self.reverse_mapping.insert(signal, var as u32);
+    }
+
+    /// Slot of `signal`, allocating a new one if it is not part of `p` yet.
+    pub fn var_for_signal(&mut self, signal: usize, name: String) -> Result<u32, EngineError> {
+        match self.reverse_mapping.get(&signal) {
+            Some(&v) => Ok(v),
+            None => {
+                let new_var = self.next_free_var().ok_or(EngineError::NoFreeSlot)?;
+                self.assign_var(new_var, signal, name);
+                Ok(new_var as u32)
+            }
+        }
     }
     
-    pub fn free_var(&mut self, var: usize) -> HashSet<Monom> {
+    pub fn free_var(&mut self, var: usize) -> Vec<u128> {
         let mapped = self.var_mapping[var];
+        self.save_slot(var);
+        self.save_reverse(mapped);
         self.reverse_mapping.remove(&mapped);
         self.var_names[var] = String::new();
-        let output = self.var_occurences[var].clone();
-        self.var_occurences[var].clear();
-        output
+        self.var_occurences.take(var)
     }
 
     pub fn get_2_compl_poly(&mut self, vars: Vec<usize>, names: Vec<String>) -> BPolynom {
@@ -430,10 +570,7 @@ impl PolyEngine {
                 factor *= -1;
             }
             if let Some(pos) = self.next_free_var() {
-                self.free_var_slots[pos] = false;
-                self.var_names[pos] = names[i].clone();
-                self.var_mapping[pos] = vars[i];
-                self.reverse_mapping.insert(vars[i], pos as u32);
+                self.assign_var(pos, vars[i], names[i].clone());
                 let new_monom = Monom::from((factor, [pos as u32]));
                 // self.var_occurences[pos].push(new_monom);
                 // println!("{}", new_monom.to_string(&self.var_names));
@@ -449,10 +586,7 @@ impl PolyEngine {
         let mut factor = 1;
         for i in 0..vars.len() {
             if let Some(pos) = self.next_free_var() {
-                self.free_var_slots[pos] = false;
-                self.var_names[pos] = names[i].clone();
-                self.var_mapping[pos] = vars[i];
-                self.reverse_mapping.insert(vars[i], pos as u32);
+                self.assign_var(pos, vars[i], names[i].clone());
                 let new_monom = Monom::from((factor, [pos as u32]));
                 // self.var_occurences[pos].push(new_monom);
                 new_poly = new_poly + new_monom;
@@ -462,390 +596,161 @@ impl PolyEngine {
         new_poly
     }
 
-    pub fn const_1_replace(&mut self, out: usize,) {
-        let &monom_var = self.reverse_mapping.get(&out).unwrap();
-        let old_var_name = self.var_names[monom_var as usize].clone();
-        let occurences = self.free_var(monom_var as usize);
-        self.free_var_slots[monom_var as usize] = true;
-        self.var_occurences[monom_var as usize].clear();
-        println!("replace {} with 1", old_var_name);
-
-        
-        for mut m in occurences {
-            let _removed = self.p.poly.remove(&m);
-            // println!("removed? {}!", removed);
-
-            for var in m.all_used_vars() {
-                self.var_occurences[var].remove(&m);
-            }
-            m.delete_var(monom_var);
-            let all_used_vars = m.all_used_vars();
-            for &var in &all_used_vars {
-                self.var_occurences[var].remove(&m);
-            }
-            self.p += &m;
-            if self.p.poly.contains(&m) {
-                for &var in &all_used_vars {
-                    self.var_occurences[var].insert(m);
-                }
-            }
+    /// Adds `delta` to the coefficient of the term with the variables `vars`
+    /// and updates the occurrence sets if the term appears or vanishes.
+    fn add_term(&mut self, vars: u128, delta: i64) {
+        let previous = self.p.add_term(vars, delta);
+        self.save_term(vars, previous);
+        let exists = previous.map_or(delta, |f| f.get() + delta) != 0;
+        if exists && previous.is_none() {
+            self.var_occurences.insert_term(vars);
+        } else if !exists && previous.is_some() {
+            self.var_occurences.remove_term(vars);
         }
     }
 
-    pub fn const_0_replace(&mut self, out: usize,) {
-        let &monom_var = self.reverse_mapping.get(&out).unwrap();
-        let old_var_name = self.var_names[monom_var as usize].clone();
-        let occurences = self.free_var(monom_var as usize);
-        self.free_var_slots[monom_var as usize] = true;
-        self.var_occurences[monom_var as usize].clear();
-        println!("replace {} with 0", old_var_name);
-
-        
-        for m in occurences {
-            let _removed = self.p.poly.remove(&m);
-            // println!("removed? {}!", removed);
-
-            for var in m.all_used_vars() {
-                self.var_occurences[var].remove(&m);
-            }
-        }
+    /// Removes every term of `p` containing `var` and returns the rest of
+    /// its variables together with its factor.
+    fn take_occurences(&mut self, var: u32) -> Vec<(u128, i64)> {
+        let occurences: Vec<u128> = self.var_occurences.iter(var as usize).collect();
+        occurences
+            .into_iter()
+            .map(|vars| {
+                let factor = self.p.coeff(vars);
+                self.add_term(vars, -factor);
+                (vars & !(1 << var), factor)
+            })
+            .collect()
     }
 
-    pub fn not_replace(&mut self, out: usize, in1: usize, in_name: String) {
-        let &monom_var = self.reverse_mapping.get(&out).unwrap();
-        let old_var_name = self.var_names[monom_var as usize].clone();
-        let occurences = self.free_var(monom_var as usize);
-        self.free_var_slots[monom_var as usize] = true;
-        self.var_occurences[monom_var as usize].clear();
-        let replacement_var = match self.reverse_mapping.get(&in1) {
-            Some(&v) => v,
-            None => {
-                let new_var = self.next_free_var().unwrap();
-                self.free_var_slots[new_var] = false;
-                self.var_names[new_var] = in_name;
-                self.var_mapping[new_var] = in1;
-                self.reverse_mapping.insert(in1, new_var as u32);
-                new_var as u32
-            }
-        };
+    /// Frees the slot of a variable that has been substituted away.
+    fn release_var(&mut self, var: u32) {
+        self.free_var(var as usize);
+        self.free_var_slots[var as usize] = true;
+    }
 
-        println!("replace {} with ¬{}", old_var_name, self.var_names[replacement_var as usize]);
+    pub fn const_1_replace(&mut self, out: usize) -> Result<(), EngineError> {
+        self.replace_var(out, GateKind::Const1, vec![])
+    }
 
-        
-        for mut m in occurences {
-            let _removed = self.p.poly.remove(&m);
-            // println!("removed? {}!", removed);
+    pub fn const_0_replace(&mut self, out: usize) -> Result<(), EngineError> {
+        self.replace_var(out, GateKind::Const0, vec![])
+    }
 
-            for var in m.all_used_vars() {
-                self.var_occurences[var].remove(&m);
-            }
-            let m_copy = m;
-            m.delete_var(monom_var);
-            let all_used_vars = m.all_used_vars();
-            for &var in &all_used_vars {
-                self.var_occurences[var].remove(&m);
-            }
-            self.p += &m;
-            if let Some(entry) = self.p.poly.get(&m) {
-                for &var in &all_used_vars {
-                    self.var_occurences[var].insert(*entry);
+    /// Fixes the given signals to constants in a single pass over the terms
+    /// containing them and frees their slots. Signals that are not part of
+    /// `p` are ignored.
+    pub fn fix_signals(&mut self, values: &HashMap<usize, bool>) -> Result<(), EngineError> {
+        self.budget.check()?;
+        let (mut zeros, mut ones) = (0_u128, 0_u128);
+        for (signal, &value) in values {
+            if let Some(&var) = self.reverse_mapping.get(signal) {
+                if value {
+                    ones |= 1 << var;
+                } else {
+                    zeros |= 1 << var;
                 }
             }
-            m.set_var(replacement_var);
-            m = (m * -1).unwrap();
-            for var in m.all_used_vars() {
-                self.var_occurences[var].remove(&m);
-            }
-            self.p += &m;
-            if let Some(entry) = self.p.poly.get(&m) {
-                for &var in &all_used_vars {
-                    self.var_occurences[var].insert(*entry);
-                }
-                self.var_occurences[replacement_var as usize].insert(*entry);
-            }
-            if !self.p.poly.contains(&m_copy) {
-                for var in all_used_vars {
-                    self.var_occurences[var].remove(&m_copy);
-                }   
+        }
+        let fixed = zeros | ones;
+        let touched: HashSet<u128> = set_bits(fixed).flat_map(|var| self.var_occurences.iter(var)).collect();
+        for vars in touched {
+            let factor = self.p.coeff(vars);
+            self.add_term(vars, -factor);
+            if vars & zeros == 0 {
+                self.add_term(vars & !ones, factor);
             }
         }
+        for var in set_bits(fixed) {
+            self.release_var(var as u32);
+        }
+        self.finish_replace()
     }
 
-    pub fn xor_replace(&mut self, out: usize, in1: usize, in_name1: String, in2: usize, in_name2: String) {
-        let &monom_var = self.reverse_mapping.get(&out).unwrap();
-        let old_var_name = self.var_names[monom_var as usize].clone();
-        let occurences = self.var_occurences[monom_var as usize].clone();
-        let replacement_var1 = match self.reverse_mapping.get(&in1) {
-            Some(&v) => v,
-            None => {
-                let new_var = self.next_free_var().unwrap();
-                self.free_var_slots[new_var] = false;
-                self.var_names[new_var] = in_name1;
-                self.var_mapping[new_var] = in1;
-                self.reverse_mapping.insert(in1, new_var as u32);
-                new_var as u32
-            }
-        };
-
-        let replacement_var2 = match self.reverse_mapping.get(&in2) {
-            Some(&v) => v,
-            None => {
-                let new_var = self.next_free_var().unwrap();
-                self.free_var_slots[new_var] = false;
-                self.var_names[new_var] = in_name2;
-                self.var_mapping[new_var] = in2;
-                self.reverse_mapping.insert(in2, new_var as u32);
-                new_var as u32
-            }
-        };
-
-        println!("replace {} with {}⨁ {}", old_var_name, self.var_names[replacement_var1 as usize], self.var_names[replacement_var2 as usize]);
-
-        
-        for mut m in occurences {
-            let _removed = self.p.poly.remove(&m);
-            println!("monom {} removed!", m.to_string(&self.var_names));
-            for var in m.all_used_vars() {
-                self.var_occurences[var].remove(&m);
-            }
-            println!("\x1B[32m");
-            self.print_var_occurences();
-            println!("\x1B[0m");
-
-            m.delete_var(monom_var);
-            let all_used_vars = m.all_used_vars();
-            let mut new_monom2 = m.clone();
-            m.set_var(replacement_var1);
-            new_monom2.set_var(replacement_var2);
-            for &var in &all_used_vars {
-                self.var_occurences[var].remove(&m);
-            }
-            println!("\x1B[32m");
-            println!("Remove {}", m.to_string(&self.var_names));
-            self.print_var_occurences();
-            println!("\x1B[0m");
-            for var in new_monom2.all_used_vars() {
-                self.var_occurences[var].remove(&new_monom2);
-            }
-            println!("\x1B[32m");
-            println!("Remove {}", new_monom2.to_string(&self.var_names));
-            self.print_var_occurences();
-            println!("\x1B[0m");
-            
-            self.p += &m;
-            if let Some(entry) = self.p.poly.get(&m) {
-                for &var in &all_used_vars {
-                    self.var_occurences[var].insert(*entry);
-                }
-                self.var_occurences[replacement_var1 as usize].insert(*entry);
-            }
-            println!("\x1B[32m");
-            println!("Added {}", m.to_string(&self.var_names));
-            self.print_var_occurences();
-            println!("\x1B[0m");
-
-
-            self.p += &new_monom2;
-            if let Some(entry) = self.p.poly.get(&new_monom2) {
-                for &var in &all_used_vars {
-                    self.var_occurences[var].insert(*entry);
-                }
-                self.var_occurences[replacement_var2 as usize].insert(*entry);
-            }
-            println!("\x1B[32m");
-            println!("Added {}", new_monom2.to_string(&self.var_names));
-            self.print_var_occurences();
-            println!("\x1B[0m");
-
-            new_monom2.set_var(replacement_var1);
-            new_monom2 = (new_monom2 * -2).unwrap();
-            for var in new_monom2.all_used_vars() {
-                self.var_occurences[var].remove(&new_monom2);
-            }
-            println!("\x1B[32m");
-            println!("Remove {}", new_monom2.to_string(&self.var_names));
-            self.print_var_occurences();
-            println!("\x1B[0m");
-            self.p += &new_monom2;
-
+    pub fn not_replace(&mut self, out: usize, in1: usize, in_name: String) -> Result<(), EngineError> {
+        self.replace_var(out, GateKind::Not, vec![(in1, in_name)])
+    }
 
-            if let Some(entry) = self.p.poly.get(&new_monom2) {
-                for &var in &all_used_vars {
-                    self.var_occurences[var].insert(*entry);
-                }
-                self.var_occurences[replacement_var1 as usize].insert(*entry);
-                self.var_occurences[replacement_var2 as usize].insert(*entry);
-            }
-            println!("\x1B[32m");
-            println!("Added {}", new_monom2.to_string(&self.var_names));
-            self.print_var_occurences();
-            println!("\x1B[0m");
+    pub fn xor_replace(&mut self, out: usize, in1: usize, in_name1: String, in2: usize, in_name2: String) -> Result<(), EngineError> {
+        self.replace_var(out, GateKind::Xor, vec![(in1, in_name1), (in2, in_name2)])
+    }
 
+    pub fn or_replace(&mut self, out: usize, in1: usize, in_name1: String, in2: usize, in_name2: String) -> Result<(), EngineError> {
+        self.replace_var(out, GateKind::Or, vec![(in1, in_name1), (in2, in_name2)])
+    }
 
+    pub fn and_replace(&mut self, out: usize, in1: usize, in_name1: String, in2: usize, in_name2: String) -> Result<(), EngineError> {
+        self.replace_var(out, GateKind::And, vec![(in1, in_name1), (in2, in_name2)])
+    }
 
+    /// Substitutes the variable of `out` by the polynomial of a `kind` gate
+    /// reading `inputs` and runs the enabled checks. The slot of `out` is
+    /// freed before the inputs are bound, so an input may take it over. Fails
+    /// without changing the engine if `out` is not part of `p` or there are
+    /// not enough free slots for the inputs.
+    fn replace_var(&mut self, out: usize, kind: GateKind, inputs: Vec<(usize, String)>) -> Result<(), EngineError> {
+        self.budget.check()?;
+        let var = *self.reverse_mapping.get(&out).ok_or(EngineError::UnknownSignal(out))?;
+        let mut unbound: Vec<usize> = inputs.iter().map(|&(s, _)| s).filter(|s| !self.reverse_mapping.contains_key(s)).collect();
+        unbound.sort();
+        unbound.dedup();
+        if unbound.len() > self.free_var_slots.iter().filter(|&&free| free).count() + 1 {
+            return Err(EngineError::NoFreeSlot);
         }
-        let occurences = self.free_var(monom_var as usize);
-        self.free_var_slots[monom_var as usize] = true;
-        self.var_occurences[monom_var as usize].clear();
-    }
-
-    pub fn or_replace(&mut self, out: usize, in1: usize, in_name1: String, in2: usize, in_name2: String) {
-        let &monom_var = self.reverse_mapping.get(&out).unwrap();
-        let old_var_name = self.var_names[monom_var as usize].clone();
-        let occurences = self.free_var(monom_var as usize);
-        self.free_var_slots[monom_var as usize] = true;
-        self.var_occurences[monom_var as usize].clear();
-        let replacement_var1 = match self.reverse_mapping.get(&in1) {
-            Some(&v) => v,
-            None => {
-                let new_var = self.next_free_var().unwrap();
-                self.free_var_slots[new_var] = false;
-                self.var_names[new_var] = in_name1;
-                self.var_mapping[new_var] = in1;
-                self.reverse_mapping.insert(in1, new_var as u32);
-                new_var as u32
-            }
-        };
-
-        let replacement_var2 = match self.reverse_mapping.get(&in2) {
-            Some(&v) => v,
-            None => {
-                let new_var = self.next_free_var().unwrap();
-                self.free_var_slots[new_var] = false;
-                self.var_names[new_var] = in_name2;
-                self.var_mapping[new_var] = in2;
-                self.reverse_mapping.insert(in2, new_var as u32);
-                new_var as u32
-            }
-        };
-
-        println!("replace {} with {}∨{}", old_var_name, self.var_names[replacement_var1 as usize], self.var_names[replacement_var2 as usize]);
-        
-        for mut m in occurences {
-            let _removed = self.p.poly.remove(&m);
-            // println!("removed? {}!", removed);
-            for var in m.all_used_vars() {
-                self.var_occurences[var].remove(&m);
-            } 
-            m.delete_var(monom_var);
-            let all_used_vars = m.all_used_vars();
-            let mut new_monom2 = m.clone();
-            m.set_var(replacement_var1);
-            new_monom2.set_var(replacement_var2);
-            for &var in &all_used_vars {
-                self.var_occurences[var].remove(&m);
-            }
-            for var in new_monom2.all_used_vars() {
-                self.var_occurences[var].remove(&new_monom2);
-            }
-            
-            self.p += &m;
-            if let Some(entry) = self.p.poly.get(&m) {
-                for &var in &all_used_vars {
-                    self.var_occurences[var].insert(*entry);
-                }
-                self.var_occurences[replacement_var1 as usize].insert(*entry);
-            }
-
-
-            self.p += &new_monom2;
-            if let Some(entry) = self.p.poly.get(&new_monom2) {
-                for &var in &all_used_vars {
-                    self.var_occurences[var].insert(*entry);
-                }
-                self.var_occurences[replacement_var2 as usize].insert(*entry);
-            }
-
-            new_monom2.set_var(replacement_var1);
-            new_monom2 = (new_monom2 * -1).unwrap();
-            for var in new_monom2.all_used_vars() {
-                self.var_occurences[var].remove(&new_monom2);
-            }
-            self.p += &new_monom2;
 
+        let before = self.self_check.then(|| self.p.clone());
+        let out_name = self.var_names[var as usize].clone();
+        let occurences = self.take_occurences(var);
+        self.release_var(var);
+        let mut input_vars = vec![];
+        for (signal, name) in inputs {
+            input_vars.push(self.var_for_signal(signal, name)?);
+        }
+        if self.trace {
+            let names: Vec<&str> = input_vars.iter().map(|&v| self.var_names[v as usize].as_str()).collect();
+            let gate = match kind {
+                GateKind::Const0 => String::from("0"),
+                GateKind::Const1 => String::from("1"),
+                GateKind::Not => format!("¬{}", names[0]),
+                GateKind::And => format!("{}·{}", names[0], names[1]),
+                GateKind::Or => format!("{}∨{}", names[0], names[1]),
+                GateKind::Xor => format!("{}⨁ {}", names[0], names[1]),
+            };
+            println!("replace {} with {}", out_name, gate);
+        }
 
-            if let Some(entry) = self.p.poly.get(&new_monom2) {
-                for &var in &all_used_vars {
-                    self.var_occurences[var].insert(*entry);
-                }
-                self.var_occurences[replacement_var1 as usize].insert(*entry);
-                self.var_occurences[replacement_var2 as usize].insert(*entry);
+        let replacement = kind.terms(&input_vars);
+        for (rest, factor) in occurences {
+            for &(f, vars) in &replacement {
+                self.add_term(rest | vars, factor * f);
             }
-
         }
+        if let Some(before) = before {
+            self.check_replacement(&before, out, var, kind, &input_vars)?;
+        }
+        self.finish_replace()
     }
 
-    pub fn and_replace(&mut self, out: usize, in1: usize, in_name1: String, in2: usize, in_name2: String) {
-        let &monom_var = self.reverse_mapping.get(&out).unwrap();
-        let old_var_name = self.var_names[monom_var as usize].clone();
-        let occurences = self.free_var(monom_var as usize);
-        self.free_var_slots[monom_var as usize] = true;
-        self.var_occurences[monom_var as usize].clear();
-        let replacement_var1 = match self.reverse_mapping.get(&in1) {
-            Some(&v) => v,
-            None => {
-                let new_var = self.next_free_var().unwrap();
-                self.free_var_slots[new_var] = false;
-                self.var_names[new_var] = in_name1;
-                self.var_mapping[new_var] = in1;
-                self.reverse_mapping.insert(in1, new_var as u32);
-                new_var as u32
-            }
-        };
-
-        let replacement_var2 = match self.reverse_mapping.get(&in2) {
-            Some(&v) => v,
-            None => {
-                let new_var = self.next_free_var().unwrap();
-                self.free_var_slots[new_var] = false;
-                self.var_names[new_var] = in_name2;
-                self.var_mapping[new_var] = in2;
-                self.reverse_mapping.insert(in2, new_var as u32);
-                new_var as u32
-            }
-        };
-
-        println!("replace {} with {}·{}", old_var_name, self.var_names[replacement_var1 as usize], self.var_names[replacement_var2 as usize]);
-        
-        for mut m in occurences {
-            let _removed = self.p.poly.remove(&m);
-            for var in m.all_used_vars() {
-                self.var_occurences[var].remove(&m);
-            }
-            // println!("removed? {}!", removed);
-            let m_copy = m;
-            m.delete_var(monom_var);
-            let all_used_vars = m.all_used_vars();
-            m.set_var(replacement_var1);
-            m.set_var(replacement_var2);
-            for var in m.all_used_vars() {
-                self.var_occurences[var].remove(&m);
-            }
-            self.p += &m;
-            if let Some(entry) = self.p.poly.get(&m) {
-                for &var in &all_used_vars {
-                    self.var_occurences[var].insert(*entry);
-                }
-                self.var_occurences[replacement_var1 as usize].insert(*entry);
-                self.var_occurences[replacement_var2 as usize].insert(*entry);
-            }
-            if !self.p.poly.contains(&m_copy) {
-                for var in all_used_vars {
-                    self.var_occurences[var].remove(&m_copy);
-                }   
-            }
+    fn finish_replace(&self) -> Result<(), EngineError> {
+        if self.debug_invariants {
+            self.check_invariants().map_err(|v| EngineError::InvariantViolated(Box::new(v)))?;
         }
+        Ok(())
     }
 
     pub fn print_var_occurences(&self) {
         println!("\n\x1B[31m--- var occurences\x1B[0m");
-        for (i, list) in self.var_occurences.iter().enumerate() {
-            if list.len() > 0 { print!("{}: ", self.var_names[i]); }
-            for o in list {
-                print!("{}, ", o.to_string(&self.var_names));
+        for i in 0..128 {
+            let list = &self.var_occurences;
+            if !list.is_empty(i) { print!("{}: ", self.var_names[i]); }
+            for o in list.iter(i) {
+                match self.p.get_term(o) {
+                    Some(m) => print!("{}, ", m.to_string(&self.var_names)),
+                    None => print!("<missing {:x}>, ", o),
+                }
             }
             
-            if list.len() > 0 { print!("\n"); }
+            if !list.is_empty(i) { println!(); }
         }
         println!("\n --- end var occurences");
     }
@@ -853,8 +758,6 @@ impl PolyEngine {
 
 #[cfg(test)]
 mod tests {
-    use std::{fmt::format};
-
     use super::*;
 
     #[test]
@@ -871,7 +774,7 @@ mod tests {
         let mut poly = BPolynom::from([(4, [7, 7]), (2, [6, 6]), (1, [5, 5]), (-3, [0, 4])]);
         println!("{}", poly.to_string(&var_names, " "));
         let monom = Monom::from((7, [3]));
-        poly = poly * monom;
+        poly *= monom;
         println!("{}", poly.to_string(&var_names, " "));
     }
 
@@ -895,6 +798,85 @@ mod tests {
         println!("  {}\n· {}\n= {}", poly1.to_string(&var_names, " "), poly2.to_string(&var_names, " "), add_poly.to_string(&var_names, " "));
     }
 
+    #[test]
+    fn poly_mult_budget() {
+        let poly1 = BPolynom::from([(-8, vec![3]), (4, vec![2]), (2, vec![1]), (1, vec![0])]);
+        let poly2 = BPolynom::from([(-8, [7]), (4, [6]), (2, [5]), (1, [4])]);
+        let product = poly1.mul_with_budget(&poly2, &Budget::unlimited()).unwrap();
+        assert_eq!(product.poly.len(), 16);
+
+        let token = CancelToken::new();
+        token.cancel();
+        let budget = Budget::unlimited().with_cancel_token(token);
+        assert_eq!(poly1.mul_with_budget(&poly2, &budget).unwrap_err(), EngineError::Cancelled);
+    }
+
+    #[test]
+    fn term_storage() {
+        let a = Monom::from((2, [1]));
+        let b = Monom::from((-3, [1]));
+        assert_ne!(a, b);
+        assert!(b < a);
+        assert!(a < Monom::from((-5, [0, 1])));
+
+        let mut poly = BPolynom::from([(2, [1]), (1, [0])]);
+        assert_eq!(poly.add_term(0b10, -2).map(|f| f.get()), Some(2));
+        assert_eq!(poly.get_term(0b10), None);
+        assert_eq!(poly.add_term(0b100, 4), None);
+        assert_eq!(poly.coeff(0b100), 4);
+        assert_eq!(poly.add_term(0b1000, 0), None);
+        assert_eq!(poly.len(), 2);
+
+        if let Entry::Occupied(mut e) = poly.entry(0b1) {
+            *e.get_mut() = NonZeroI64::new(7).unwrap();
+        }
+        assert_eq!(poly, BPolynom::from([(4, [2]), (7, [0])]));
+    }
+
+    #[test]
+    fn fix_signals_in_one_pass() {
+        let m = generate::multiplier(3);
+        let mut en = generate::multiplier_engine(&m);
+        let mut fixed = HashMap::from([(m.a[2], false), (m.b[0], true), (m.b[1], false), (m.b[2], false)]);
+        fixed.insert(12345, true);
+        en.debug_invariants = true;
+        en.fix_signals(&fixed).unwrap();
+
+        // Z - A·1 with A limited to two bits
+        let expected = en.parse_poly(&format!(
+            "{} - n{} - 2*n{}",
+            m.z.iter().enumerate().map(|(i, s)| format!("{}*n{}", 1 << i, s)).collect::<Vec<_>>().join(" + "),
+            m.a[0],
+            m.a[1]
+        ));
+        assert_eq!(en.p, expected.unwrap());
+        assert_eq!(en.reverse_mapping.len(), m.z.len() + 2);
+        assert_eq!(en.free_var_slots.iter().filter(|&&f| !f).count(), m.z.len() + 2);
+    }
+
+    #[test]
+    fn replace_with_all_slots_bound() {
+        // x0 + ... + x127 with every slot bound to signal 1000 + slot
+        let mut en = PolyEngine::new(BPolynom::empty());
+        for v in 0..128 {
+            en.assign_var(v, 1000 + v, format!("x{}", v));
+        }
+        en.add_from_generates(BPolynom::from((0..128).map(|v| (1, vec![v]))));
+        en.debug_invariants = true;
+        let before = en.p.clone();
+
+        assert_eq!(en.and_replace(1000, 1, "a".into(), 2, "b".into()), Err(EngineError::NoFreeSlot));
+        assert_eq!(en.not_replace(5, 1, "a".into()), Err(EngineError::UnknownSignal(5)));
+        assert_eq!(en.p, before);
+
+        // the input takes over the slot of the output
+        en.not_replace(1000, 1, "a".into()).unwrap();
+        assert_eq!(en.reverse_mapping[&1], 0);
+        assert_eq!(en.p, &before + &BPolynom::from([(1, vec![]), (-2, vec![0])]));
+        en.and_replace(1001, 1, "a".into(), 1127, "x127".into()).unwrap();
+        assert!(en.free_var_slots[1] && en.p.coeff(1 << 127 | 1) == 1);
+    }
+
     #[test]
     fn test_engine() {
         let mut en = PolyEngine::new(BPolynom::empty());
@@ -913,25 +895,25 @@ mod tests {
         println!("{:?}", en.var_names);
         println!("{:?}", en.reverse_mapping);
         println!("{:?}", en.free_var_slots);
-        en.not_replace(208, 211, String::from("kuhkacke"));
+        en.not_replace(208, 211, String::from("kuhkacke")).unwrap();
         println!("{}", en.p.to_string(&en.var_names, " "));
         println!("{:?}", en.var_mapping);
         println!("{:?}", en.var_names);
         println!("{:?}", en.reverse_mapping);
         println!("{:?}", en.free_var_slots);
-        en.xor_replace(207, 255, String::from("pf1"), 256, String::from("pf2"));
+        en.xor_replace(207, 255, String::from("pf1"), 256, String::from("pf2")).unwrap();
         println!("{}", en.p.to_string(&en.var_names, " "));
         println!("{:?}", en.var_mapping);
         println!("{:?}", en.var_names);
         println!("{:?}", en.reverse_mapping);
         println!("{:?}", en.free_var_slots);
-        en.or_replace(256, 260, String::from("or1"), 261, String::from("or2"));
+        en.or_replace(256, 260, String::from("or1"), 261, String::from("or2")).unwrap();
         println!("{}", en.p.to_string(&en.var_names, "\n"));
         println!("{:?}", en.var_mapping);
         println!("{:?}", en.var_names);
         println!("{:?}", en.reverse_mapping);
         println!("{:?}", en.free_var_slots);
-        en.and_replace(211, 270, String::from("dreck"), 271, String::from("vogel"));
+        en.and_replace(211, 270, String::from("dreck"), 271, String::from("vogel")).unwrap();
         println!("{}", en.p.to_string(&en.var_names, "\n"));
         println!("{:?}", en.var_mapping);
         println!("{:?}", en.var_names);
@@ -957,9 +939,9 @@ mod tests {
         println!("{}", b.to_string(&en.var_names, " "));
         en.add_from_generates(sum);
         println!("{}", en.p.to_string(&en.var_names, " "));
-        en.and_replace(208, 209, "A0".into(), 210, "B0".into());
+        en.and_replace(208, 209, "A0".into(), 210, "B0".into()).unwrap();
         println!("{}", en.p.to_string(&en.var_names, " "));
-        en.xor_replace(207, 209, "A0".into(), 210, "B0".into());
+        en.xor_replace(207, 209, "A0".into(), 210, "B0".into()).unwrap();
         println!("{}", en.p.to_string(&en.var_names, " "));
     }
 
@@ -982,19 +964,19 @@ mod tests {
         en.add_from_generates(sum);
         println!("{}", en.p.to_string(&en.var_names, " "));
         en.print_var_occurences();
-        en.or_replace(208, 211, "G0".into(), 212, "G1".into());
+        en.or_replace(208, 211, "G0".into(), 212, "G1".into()).unwrap();
         println!("{}", en.p.to_string(&en.var_names, " "));
         en.print_var_occurences();
-        en.and_replace(212, 213, "C0".into(), 214, "G2".into());
+        en.and_replace(212, 213, "C0".into(), 214, "G2".into()).unwrap();
         println!("{}", en.p.to_string(&en.var_names, " "));
         en.print_var_occurences();
-        en.xor_replace(207, 213, "C0".into(), 214, "G2".into());
+        en.xor_replace(207, 213, "C0".into(), 214, "G2".into()).unwrap();
         println!("{}", en.p.to_string(&en.var_names, " "));
         en.print_var_occurences();
-        en.and_replace(211, 209, "A0".into(), 210, "B0".into());
+        en.and_replace(211, 209, "A0".into(), 210, "B0".into()).unwrap();
         println!("{}", en.p.to_string(&en.var_names, " "));
         en.print_var_occurences();
-        en.xor_replace(214, 209, "A0".into(), 210, "B0".into());
+        en.xor_replace(214, 209, "A0".into(), 210, "B0".into()).unwrap();
         println!("{}", en.p.to_string(&en.var_names, " "));
         en.print_var_occurences();
     }
@@ -1018,28 +1000,28 @@ mod tests {
         en.add_from_generates(sum);
         println!("{}", en.p.to_string(&en.var_names, " "));
 
-        en.or_replace(208, 211, "G0".into(), 212, "G1".into());
+        en.or_replace(208, 211, "G0".into(), 212, "G1".into()).unwrap();
         println!("{}\n", en.p.to_string(&en.var_names, " "));
 
-        en.and_replace(212, 213, "C0".into(), 214, "G2".into());
+        en.and_replace(212, 213, "C0".into(), 214, "G2".into()).unwrap();
         println!("{}\n", en.p.to_string(&en.var_names, " "));
 
-        en.xor_replace(207, 213, "C0".into(), 214, "G2".into());
+        en.xor_replace(207, 213, "C0".into(), 214, "G2".into()).unwrap();
         println!("{}\n", en.p.to_string(&en.var_names, " "));
 
-        en.and_replace(211, 209, "A0".into(), 210, "B0".into());
+        en.and_replace(211, 209, "A0".into(), 210, "B0".into()).unwrap();
         println!("{}\n", en.p.to_string(&en.var_names, " "));
 
-        en.xor_replace(214, 209, "A0".into(), 210, "B0".into());
+        en.xor_replace(214, 209, "A0".into(), 210, "B0".into()).unwrap();
         println!("{}\n", en.p.to_string(&en.var_names, " "));
 
-        en.const_1_replace(213);
+        en.const_1_replace(213).unwrap();
         println!("{}\n", en.p.to_string(&en.var_names, " "));
 
-        en.const_1_replace(209);
+        en.const_1_replace(209).unwrap();
         println!("{}\n", en.p.to_string(&en.var_names, " "));
 
-        en.const_0_replace(210);
+        en.const_0_replace(210).unwrap();
         println!("{}\n", en.p.to_string(&en.var_names, " "));
     }
 }
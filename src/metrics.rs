@@ -0,0 +1,268 @@
+//! Error metrics of an error polynomial such as the remainder `Z - A·B` of
+//! an approximate multiplier, for independent inputs. Means are exact for
+//! few variables and sampled otherwise.
+
+use std::collections::HashMap;
+
+use crate::bounds::BoundsConfig;
+use crate::eval::Lanes;
+use crate::table::{value_table, MAX_TABLE_VARS};
+use crate::{set_bits, BPolynom, PolyEngine, Rng};
+
+/// Independent input distribution over the variable slots.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Distribution {
+    /// Probability of each slot being 1.
+    pub probabilities: [f64; 128],
+}
+
+impl Distribution {
+    pub fn uniform() -> Self {
+        Distribution { probabilities: [0.5; 128] }
+    }
+}
+
+impl Default for Distribution {
+    fn default() -> Self {
+        Distribution::uniform()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MetricsConfig {
+    /// Polynomials with at most this many variables are enumerated, capped
+    /// at [`MAX_TABLE_VARS`].
+    pub exact_vars: u32,
+    /// Number of random assignments drawn otherwise.
+    pub samples: u64,
+    pub seed: u64,
+    pub bounds: BoundsConfig,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        MetricsConfig { exact_vars: 20, samples: 1 << 16, seed: 0x5eed, bounds: BoundsConfig::default() }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ErrorMetrics {
+    /// Bound of the absolute error over all assignments that can occur.
+    pub worst_case: i128,
+    /// Whether `worst_case` is attained by some assignment.
+    pub worst_case_exact: bool,
+    pub mean_absolute: f64,
+    pub mean_squared: f64,
+    /// Probability of a non-zero error.
+    pub error_rate: f64,
+    /// Number of samples the means were estimated from, `None` if they are
+    /// exact.
+    pub samples: Option<u64>,
+}
+
+impl PolyEngine {
+    /// Distribution of the slots bound to the signals in `probabilities`,
+    /// all other slots are uniform.
+    pub fn distribution(&self, probabilities: &HashMap<usize, f64>) -> Distribution {
+        let mut dist = Distribution::uniform();
+        for (signal, &p) in probabilities {
+            if let Some(&var) = self.reverse_mapping.get(signal) {
+                dist.probabilities[var as usize] = p;
+            }
+        }
+        dist
+    }
+}
+
+impl BPolynom {
+    /// Error metrics of `p` read as an error term under `dist`.
+    pub fn error_metrics(&self, dist: &Distribution, config: &MetricsConfig) -> ErrorMetrics {
+        // variables that are constant under `dist` are fixed first, so that
+        // the worst case only covers assignments that can occur
+        let mut p = self.clone();
+        let support = self.support();
+        for var in set_bits(support) {
+            let prob = dist.probabilities[var];
+            if prob <= 0.0 || prob >= 1.0 {
+                p = p.cofactor(var as u32, prob >= 1.0);
+            }
+        }
+        let bounds = p.bounds_with(&config.bounds);
+        let mut metrics = ErrorMetrics {
+            worst_case: bounds.max_abs(),
+            worst_case_exact: bounds.exact,
+            mean_absolute: 0.0,
+            mean_squared: 0.0,
+            error_rate: 0.0,
+            samples: None,
+        };
+
+        let vars: Vec<usize> = set_bits(p.support()).collect();
+        let mut add = |value: i128, weight: f64| {
+            let v = value as f64;
+            metrics.mean_absolute += weight * v.abs();
+            metrics.mean_squared += weight * v * v;
+            if value != 0 {
+                metrics.error_rate += weight;
+            }
+        };
+        if vars.len() <= config.exact_vars.min(MAX_TABLE_VARS as u32) as usize {
+            let mut weights = vec![1.0; 1 << vars.len()];
+            for (j, &var) in vars.iter().enumerate() {
+                let prob = dist.probabilities[var];
+                for i in 0..1 << j {
+                    weights[i | 1 << j] = weights[i] * prob;
+                    weights[i] *= 1.0 - prob;
+                }
+            }
+            for (value, weight) in value_table(&p, &vars).into_iter().zip(weights) {
+                add(value, weight);
+            }
+        } else {
+            let mut rng = Rng::new(config.seed);
+            let weight = 1.0 / config.samples as f64;
+            let mut remaining = config.samples;
+            while remaining > 0 {
+                let mut lanes = [0_u128; 128];
+                for &var in &vars {
+                    lanes[var] = random_lanes(&mut rng, dist.probabilities[var]);
+                }
+                let batch = remaining.min(u128::WIDTH as u64);
+                for &value in &p.eval_lanes(&lanes)[..batch as usize] {
+                    add(value, weight);
+                }
+                remaining -= batch;
+            }
+            metrics.samples = Some(config.samples);
+        }
+        metrics
+    }
+}
+
+impl BPolynom {
+    /// Expected value of `p` under `dist`. By multilinearity and independence
+    /// this is the sum of the factors weighted with the probabilities of
+    /// their terms being 1.
+    pub fn expectation(&self, dist: &Distribution) -> f64 {
+        self.terms().map(|m| m.factor.get() as f64 * term_probability(m.var_product, dist)).sum()
+    }
+
+    /// Variance of `p` under `dist`. Terms without common variables are
+    /// independent, so only pairs of overlapping terms contribute; the cost
+    /// grows with their number, i.e. with the degree and the sharing of
+    /// variables rather than with the square of the number of terms.
+    pub fn variance(&self, dist: &Distribution) -> f64 {
+        let terms: Vec<(u128, f64, f64)> =
+            self.poly.iter().map(|(&vars, f)| (vars, f.get() as f64, term_probability(vars, dist))).collect();
+        let mut by_var: Vec<Vec<usize>> = vec![vec![]; 128];
+        for (i, &(vars, _, _)) in terms.iter().enumerate() {
+            for v in set_bits(vars) {
+                by_var[v].push(i);
+            }
+        }
+        let mut variance = 0.0;
+        let mut seen = vec![usize::MAX; terms.len()];
+        for (i, &(s, cs, ps)) in terms.iter().enumerate() {
+            for v in set_bits(s) {
+                for &j in &by_var[v] {
+                    if seen[j] == i {
+                        continue;
+                    }
+                    seen[j] = i;
+                    let (t, ct, pt) = terms[j];
+                    variance += cs * ct * (term_probability(s | t, dist) - ps * pt);
+                }
+            }
+        }
+        variance
+    }
+}
+
+fn term_probability(vars: u128, dist: &Distribution) -> f64 {
+    set_bits(vars).map(|v| dist.probabilities[v]).product()
+}
+
+/// 128 independent bits, each set with probability `prob`.
+pub(crate) fn random_lanes(rng: &mut Rng, prob: f64) -> u128 {
+    if prob == 0.5 {
+        return rng.next_u128();
+    }
+    (0..128).filter(|_| rng.next_f64() < prob).fold(0, |acc, lane| acc | 1 << lane)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::{broken_multiplier, random_poly};
+
+    #[test]
+    fn metrics_of_approximate_multiplier() {
+        // drop the partial product a0·b0
+        let (m, en) = broken_multiplier(2, &[(0, 0)]);
+        assert_eq!(en.p.len(), 1);
+
+        let metrics = en.p.error_metrics(&Distribution::uniform(), &MetricsConfig::default());
+        assert_eq!(
+            metrics,
+            ErrorMetrics {
+                worst_case: 1,
+                worst_case_exact: true,
+                mean_absolute: 0.25,
+                mean_squared: 0.25,
+                error_rate: 0.25,
+                samples: None
+            }
+        );
+
+        let dist = en.distribution(&HashMap::from([(m.a[0], 1.0), (m.b[0], 0.25)]));
+        let metrics = en.p.error_metrics(&dist, &MetricsConfig::default());
+        assert_eq!((metrics.worst_case, metrics.mean_absolute, metrics.error_rate), (1, 0.25, 0.25));
+
+        let dist = en.distribution(&HashMap::from([(m.a[0], 0.0)]));
+        let metrics = en.p.error_metrics(&dist, &MetricsConfig::default());
+        assert_eq!((metrics.worst_case, metrics.error_rate), (0, 0.0));
+    }
+
+    #[test]
+    fn expectation_and_variance() {
+        let mut dist = Distribution::uniform();
+        dist.probabilities[0] = 0.3;
+        dist.probabilities[1] = 0.6;
+        let xor = BPolynom::from([(1, vec![0]), (1, vec![1]), (-2, vec![0, 1])]);
+        let e = xor.expectation(&dist);
+        assert!((e - 0.54).abs() < 1e-12);
+        assert!((xor.variance(&dist) - e * (1.0 - e)).abs() < 1e-12);
+
+        let mut rng = Rng::new(3);
+        for _ in 0..10 {
+            let p = random_poly(&mut rng, 10, 0xff, 20);
+            for v in 0..8 {
+                dist.probabilities[v] = rng.next_f64();
+            }
+            let metrics = p.error_metrics(&dist, &MetricsConfig::default());
+            let e = p.expectation(&dist);
+            assert!((p.variance(&dist) - (metrics.mean_squared - e * e)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn sampled_metrics() {
+        // 2·x0·x1 - x2
+        let p = BPolynom::from([(2, vec![0, 1]), (-1, vec![2])]);
+        let exact = p.error_metrics(&Distribution::uniform(), &MetricsConfig::default());
+        assert_eq!((exact.mean_absolute, exact.mean_squared, exact.error_rate), (0.75, 1.0, 0.625));
+
+        let config = MetricsConfig { exact_vars: 0, samples: 20_000, ..Default::default() };
+        let sampled = p.error_metrics(&Distribution::uniform(), &config);
+        assert_eq!(sampled.samples, Some(20_000));
+        assert_eq!(sampled.worst_case, 2);
+        assert!((sampled.mean_absolute - exact.mean_absolute).abs() < 0.03);
+        assert!((sampled.mean_squared - exact.mean_squared).abs() < 0.05);
+        assert!((sampled.error_rate - exact.error_rate).abs() < 0.03);
+
+        let mut dist = Distribution::uniform();
+        dist.probabilities[2] = 0.9;
+        let sampled = p.error_metrics(&dist, &config);
+        assert!((sampled.error_rate - (1.0 - 0.75 * 0.1)).abs() < 0.03);
+    }
+}
@@ -0,0 +1,224 @@
+//! Occurrence index: for every variable slot, the variable sets of the terms
+//! containing it. Terms get ids in an arena and every slot keeps a bitmap
+//! over the ids, so a term is added to or removed from all its slots with a
+//! single hash lookup.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use array_init::array_init;
+
+use crate::set_bits;
+
+#[derive(Clone)]
+pub struct OccurrenceIndex {
+    /// Variable set of every id, ids in `free_ids` are unused.
+    terms: Vec<u128>,
+    /// Number of slots whose bitmap contains the id.
+    refs: Vec<u8>,
+    ids: HashMap<u128, u32>,
+    free_ids: Vec<u32>,
+    bitmaps: [Vec<u64>; 128],
+    lens: [usize; 128],
+}
+
+impl Default for OccurrenceIndex {
+    fn default() -> Self {
+        OccurrenceIndex {
+            terms: vec![],
+            refs: vec![],
+            ids: HashMap::new(),
+            free_ids: vec![],
+            bitmaps: array_init(|_| vec![]),
+            lens: [0; 128],
+        }
+    }
+}
+
+impl OccurrenceIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of terms containing `var`.
+    pub fn len(&self, var: usize) -> usize {
+        self.lens[var]
+    }
+
+    pub fn is_empty(&self, var: usize) -> bool {
+        self.lens[var] == 0
+    }
+
+    pub fn contains(&self, var: usize, vars: u128) -> bool {
+        self.ids.get(&vars).is_some_and(|&id| self.is_set(var, id))
+    }
+
+    /// Variable sets of the terms containing `var`, in no particular order.
+    pub fn iter(&self, var: usize) -> impl Iterator<Item = u128> + '_ {
+        self.bitmaps[var].iter().enumerate().flat_map(move |(i, &word)| {
+            set_bits(word as u128).map(move |bit| self.terms[i * 64 + bit])
+        })
+    }
+
+    /// Adds the term `vars` to the slot `var`, returns whether it was new.
+    pub fn insert(&mut self, var: usize, vars: u128) -> bool {
+        let id = self.id(vars);
+        self.set(var, id)
+    }
+
+    /// Removes the term `vars` from the slot `var`, returns whether it was
+    /// present.
+    pub fn remove(&mut self, var: usize, vars: u128) -> bool {
+        match self.ids.get(&vars) {
+            Some(&id) => self.unset(var, id),
+            None => false,
+        }
+    }
+
+    /// Adds the term `vars` to all of its slots.
+    pub fn insert_term(&mut self, vars: u128) {
+        if vars != 0 {
+            let id = self.id(vars);
+            for var in set_bits(vars) {
+                self.set(var, id);
+            }
+        }
+    }
+
+    /// Removes the term `vars` from all of its slots.
+    pub fn remove_term(&mut self, vars: u128) {
+        if let Some(&id) = self.ids.get(&vars) {
+            for var in set_bits(vars) {
+                self.unset(var, id);
+            }
+        }
+    }
+
+    /// Removes all terms from the slot `var` and returns them.
+    pub fn take(&mut self, var: usize) -> Vec<u128> {
+        let taken: Vec<u128> = self.iter(var).collect();
+        for &vars in &taken {
+            self.remove(var, vars);
+        }
+        taken
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    fn id(&mut self, vars: u128) -> u32 {
+        if let Some(&id) = self.ids.get(&vars) {
+            return id;
+        }
+        let id = match self.free_ids.pop() {
+            Some(id) => {
+                self.terms[id as usize] = vars;
+                id
+            }
+            None => {
+                self.terms.push(vars);
+                self.refs.push(0);
+                self.terms.len() as u32 - 1
+            }
+        };
+        self.ids.insert(vars, id);
+        id
+    }
+
+    fn is_set(&self, var: usize, id: u32) -> bool {
+        self.bitmaps[var].get(id as usize / 64).is_some_and(|word| word >> (id % 64) & 1 == 1)
+    }
+
+    fn set(&mut self, var: usize, id: u32) -> bool {
+        if self.is_set(var, id) {
+            return false;
+        }
+        let bitmap = &mut self.bitmaps[var];
+        let word = id as usize / 64;
+        if bitmap.len() <= word {
+            bitmap.resize(word + 1, 0);
+        }
+        bitmap[word] |= 1 << (id % 64);
+        self.lens[var] += 1;
+        self.refs[id as usize] += 1;
+        true
+    }
+
+    /// Clears the bit of `id` for `var` and frees the id once no slot
+    /// refers to it.
+    fn unset(&mut self, var: usize, id: u32) -> bool {
+        if !self.is_set(var, id) {
+            return false;
+        }
+        self.bitmaps[var][id as usize / 64] &= !(1 << (id % 64));
+        self.lens[var] -= 1;
+        self.refs[id as usize] -= 1;
+        if self.refs[id as usize] == 0 {
+            self.ids.remove(&self.terms[id as usize]);
+            self.free_ids.push(id);
+        }
+        true
+    }
+
+    /// Terms of every slot, sorted, for comparisons and debug output.
+    fn sorted(&self) -> Vec<Vec<u128>> {
+        (0..128)
+            .map(|var| {
+                let mut terms: Vec<u128> = self.iter(var).collect();
+                terms.sort();
+                terms
+            })
+            .collect()
+    }
+}
+
+impl PartialEq for OccurrenceIndex {
+    fn eq(&self, other: &Self) -> bool {
+        self.lens == other.lens && self.sorted() == other.sorted()
+    }
+}
+
+impl Eq for OccurrenceIndex {}
+
+impl fmt::Debug for OccurrenceIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.sorted().iter().enumerate().filter(|(_, terms)| !terms.is_empty())).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_are_shared_and_reused() {
+        let mut index = OccurrenceIndex::new();
+        index.insert_term(0b101);
+        index.insert_term(0b110);
+        index.insert_term(0);
+        assert_eq!((index.len(0), index.len(1), index.len(2)), (1, 1, 2));
+        assert!(index.contains(2, 0b101) && !index.contains(1, 0b101));
+
+        index.remove(0, 0b101);
+        assert!(index.contains(2, 0b101));
+        index.remove_term(0b101);
+        assert!(index.is_empty(0) && !index.contains(2, 0b101));
+        index.insert_term(0b1000);
+        assert_eq!(index.terms.len(), 2);
+
+        // a slot may list a term that does not contain it
+        assert!(index.insert(5, 0b110) && !index.insert(5, 0b110));
+        assert_eq!(index.take(2), [0b110]);
+        assert_eq!(index.iter(5).collect::<Vec<_>>(), [0b110]);
+        index.remove_term(0b110);
+        assert!(index.contains(5, 0b110));
+        assert!(index.remove(5, 0b110) && index.ids.len() == 1);
+
+        let mut other = OccurrenceIndex::new();
+        other.insert_term(0b1000);
+        assert_eq!(index, other);
+        other.clear();
+        assert_ne!(index, other);
+    }
+}
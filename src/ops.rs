@@ -0,0 +1,340 @@
+//! Arithmetic operators on `BPolynom` for all combinations of owned and
+//! borrowed operands. The by-value and assigning forms reuse the left
+//! operand's storage.
+//!
+//! The logical operators treat both operands as Boolean-valued, i.e. as
+//! evaluating to 0 or 1 on every assignment, and build the same gate
+//! polynomials the `*_replace` methods substitute: `p & q = p·q`,
+//! `p | q = p + q - p·q`, `p ^ q = p + q - 2·p·q` and `!p = 1 - p`. For
+//! other polynomials the results have no logical meaning.
+
+use std::mem;
+use std::ops::{Add, AddAssign, BitAnd, BitOr, BitXor, Mul, MulAssign, Neg, Not, Sub, SubAssign};
+
+use crate::{BPolynom, Monom};
+
+impl AddAssign<&BPolynom> for BPolynom {
+    fn add_assign(&mut self, rhs: &BPolynom) {
+        for (&var_product, factor) in rhs.poly.iter() {
+            self.add_term(var_product, factor.get());
+        }
+    }
+}
+
+impl AddAssign<BPolynom> for BPolynom {
+    fn add_assign(&mut self, mut rhs: BPolynom) {
+        if rhs.len() > self.len() {
+            mem::swap(self, &mut rhs);
+        }
+        *self += &rhs;
+    }
+}
+
+impl SubAssign<&BPolynom> for BPolynom {
+    fn sub_assign(&mut self, rhs: &BPolynom) {
+        for (&var_product, factor) in rhs.poly.iter() {
+            self.add_term(var_product, -factor.get());
+        }
+    }
+}
+
+impl SubAssign<BPolynom> for BPolynom {
+    fn sub_assign(&mut self, rhs: BPolynom) {
+        *self -= &rhs;
+    }
+}
+
+impl SubAssign<&Monom> for BPolynom {
+    fn sub_assign(&mut self, rhs: &Monom) {
+        self.add_term(rhs.var_product, -rhs.factor.get());
+    }
+}
+
+impl MulAssign<&BPolynom> for BPolynom {
+    fn mul_assign(&mut self, rhs: &BPolynom) {
+        *self = &*self * rhs;
+    }
+}
+
+impl MulAssign<BPolynom> for BPolynom {
+    fn mul_assign(&mut self, rhs: BPolynom) {
+        *self = &*self * &rhs;
+    }
+}
+
+impl MulAssign<i64> for BPolynom {
+    fn mul_assign(&mut self, rhs: i64) {
+        *self = mem::take(self) * rhs;
+    }
+}
+
+impl MulAssign<Monom> for BPolynom {
+    fn mul_assign(&mut self, rhs: Monom) {
+        *self = mem::take(self) * rhs;
+    }
+}
+
+impl Neg for BPolynom {
+    type Output = BPolynom;
+
+    fn neg(mut self) -> Self::Output {
+        for factor in self.poly.values_mut() {
+            *factor = -*factor;
+        }
+        self
+    }
+}
+
+impl Neg for &BPolynom {
+    type Output = BPolynom;
+
+    fn neg(self) -> Self::Output {
+        -self.clone()
+    }
+}
+
+impl Sub<Monom> for BPolynom {
+    type Output = BPolynom;
+
+    fn sub(mut self, rhs: Monom) -> Self::Output {
+        self -= &rhs;
+        self
+    }
+}
+
+impl Add<&BPolynom> for BPolynom {
+    type Output = BPolynom;
+
+    fn add(mut self, rhs: &BPolynom) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+impl Add<BPolynom> for BPolynom {
+    type Output = BPolynom;
+
+    fn add(mut self, rhs: BPolynom) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+impl Add<&BPolynom> for &BPolynom {
+    type Output = BPolynom;
+
+    fn add(self, rhs: &BPolynom) -> Self::Output {
+        self.clone() + rhs
+    }
+}
+
+impl Add<BPolynom> for &BPolynom {
+    type Output = BPolynom;
+
+    fn add(self, rhs: BPolynom) -> Self::Output {
+        rhs + self
+    }
+}
+
+impl Sub<&BPolynom> for BPolynom {
+    type Output = BPolynom;
+
+    fn sub(mut self, rhs: &BPolynom) -> Self::Output {
+        self -= rhs;
+        self
+    }
+}
+
+impl Sub<BPolynom> for BPolynom {
+    type Output = BPolynom;
+
+    fn sub(mut self, rhs: BPolynom) -> Self::Output {
+        self -= &rhs;
+        self
+    }
+}
+
+impl Sub<&BPolynom> for &BPolynom {
+    type Output = BPolynom;
+
+    fn sub(self, rhs: &BPolynom) -> Self::Output {
+        self.clone() - rhs
+    }
+}
+
+impl Sub<BPolynom> for &BPolynom {
+    type Output = BPolynom;
+
+    fn sub(self, rhs: BPolynom) -> Self::Output {
+        -rhs + self
+    }
+}
+
+impl Mul<&BPolynom> for BPolynom {
+    type Output = BPolynom;
+
+    fn mul(self, rhs: &BPolynom) -> Self::Output {
+        &self * rhs
+    }
+}
+
+impl Mul<BPolynom> for BPolynom {
+    type Output = BPolynom;
+
+    fn mul(self, rhs: BPolynom) -> Self::Output {
+        &self * &rhs
+    }
+}
+
+impl Mul<BPolynom> for &BPolynom {
+    type Output = BPolynom;
+
+    fn mul(self, rhs: BPolynom) -> Self::Output {
+        self * &rhs
+    }
+}
+
+impl Mul<i64> for &BPolynom {
+    type Output = BPolynom;
+
+    fn mul(self, rhs: i64) -> Self::Output {
+        self.clone() * rhs
+    }
+}
+
+impl Mul<Monom> for &BPolynom {
+    type Output = BPolynom;
+
+    fn mul(self, rhs: Monom) -> Self::Output {
+        let mut product = BPolynom::empty();
+        for (&var_product, factor) in self.poly.iter() {
+            product.add_term(var_product | rhs.var_product, factor.get() * rhs.factor.get());
+        }
+        product
+    }
+}
+
+impl BitAnd<&BPolynom> for &BPolynom {
+    type Output = BPolynom;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn bitand(self, rhs: &BPolynom) -> Self::Output {
+        self * rhs
+    }
+}
+
+impl BitOr<&BPolynom> for &BPolynom {
+    type Output = BPolynom;
+
+    fn bitor(self, rhs: &BPolynom) -> Self::Output {
+        self + rhs - self * rhs
+    }
+}
+
+impl BitXor<&BPolynom> for &BPolynom {
+    type Output = BPolynom;
+
+    fn bitxor(self, rhs: &BPolynom) -> Self::Output {
+        self + rhs - (self * rhs) * 2
+    }
+}
+
+impl Not for &BPolynom {
+    type Output = BPolynom;
+
+    fn not(self) -> Self::Output {
+        -self + Monom::from((1, []))
+    }
+}
+
+impl Not for BPolynom {
+    type Output = BPolynom;
+
+    fn not(self) -> Self::Output {
+        -self + Monom::from((1, []))
+    }
+}
+
+macro_rules! forward_logical_op {
+    ($($imp:ident $method:ident),*) => {$(
+        impl $imp<BPolynom> for BPolynom {
+            type Output = BPolynom;
+
+            fn $method(self, rhs: BPolynom) -> Self::Output {
+                (&self).$method(&rhs)
+            }
+        }
+
+        impl $imp<&BPolynom> for BPolynom {
+            type Output = BPolynom;
+
+            fn $method(self, rhs: &BPolynom) -> Self::Output {
+                (&self).$method(rhs)
+            }
+        }
+
+        impl $imp<BPolynom> for &BPolynom {
+            type Output = BPolynom;
+
+            fn $method(self, rhs: BPolynom) -> Self::Output {
+                self.$method(&rhs)
+            }
+        }
+    )*};
+}
+
+forward_logical_op!(BitAnd bitand, BitOr bitor, BitXor bitxor);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn operator_identities() {
+        let p = BPolynom::from([(3, vec![0, 1]), (-2, vec![2]), (5, vec![])]);
+        let q = BPolynom::from([(1, vec![0]), (2, vec![2]), (-1, vec![1, 3])]);
+
+        assert!((&p - &p).is_empty());
+        assert_eq!(-(-p.clone()), p);
+        assert_eq!(&p + &q, &q + &p);
+        assert_eq!(p.clone() + q.clone(), &p + q.clone());
+        assert_eq!(&p - &q, -(&q - &p));
+        assert_eq!(p.clone() - q.clone(), &p - q.clone());
+        assert_eq!(&p * &q, q.clone() * p.clone());
+        assert_eq!(&(&p + &q) * &q, &p * &q + &q * &q);
+        assert_eq!(&p * 3, p.clone() * 3);
+        assert_eq!(&p * Monom::from((2, [4])), p.clone() * Monom::from((2, [4])));
+
+        let mut r = p.clone();
+        r += &q;
+        r -= q.clone();
+        assert_eq!(r, p);
+        r *= &q;
+        assert_eq!(r, &p * &q);
+        r *= -1;
+        r += p.clone() * q.clone();
+        assert!(r.is_empty());
+        r -= &Monom::from((4, [0]));
+        r *= Monom::from((1, [0, 1]));
+        assert_eq!(r, BPolynom::from([(-4, [0, 1])]));
+        assert_eq!(r - Monom::from((-4, [1, 0])), BPolynom::empty());
+    }
+
+    #[test]
+    fn logical_operators() {
+        let (a, b, c) = (BPolynom::from([(1, [0])]), BPolynom::from([(1, [1])]), BPolynom::from([(1, [2])]));
+        assert_eq!(&a ^ &b, BPolynom::from([(1, vec![0]), (1, vec![1]), (-2, vec![0, 1])]));
+        assert_eq!(!&a, BPolynom::from([(1, vec![]), (-1, vec![0])]));
+
+        let maj = (&a & &b) | (&a & &c) | (&b & &c);
+        let sum = &a ^ &b ^ c.clone();
+        let mux = (&a & !c.clone()) | (b.clone() & &c);
+        for x in 0..8_u128 {
+            let bit = |v: u128| (x >> v & 1) as i128;
+            assert_eq!(maj.eval(x), (bit(0) + bit(1) + bit(2) >= 2) as i128);
+            assert_eq!(sum.eval(x), bit(0) ^ bit(1) ^ bit(2));
+            assert_eq!(mux.eval(x), if bit(2) == 1 { bit(1) } else { bit(0) });
+        }
+        assert_eq!(!!a.clone(), a);
+    }
+}
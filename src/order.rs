@@ -0,0 +1,195 @@
+//! Monomial orders for listing the terms of a polynomial canonically and for
+//! leading-term queries. Unless stated otherwise variables are ordered by
+//! slot, the highest slot being the largest variable, which is also the order
+//! `Monom::to_string` prints them in.
+
+use std::cmp::Ordering;
+
+use crate::rewrite::{CycleError, Gate, Schedule};
+use crate::{BPolynom, Monom, PolyEngine};
+
+/// Total order on variable sets. Terms are listed from the largest to the
+/// smallest one.
+pub trait MonomialOrder {
+    fn cmp(&self, a: u128, b: u128) -> Ordering;
+}
+
+/// Higher degree first, equal degrees compared lexicographically. Used for
+/// printing, `Debug` and serialization.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DegLex;
+
+impl MonomialOrder for DegLex {
+    fn cmp(&self, a: u128, b: u128) -> Ordering {
+        a.count_ones().cmp(&b.count_ones()).then(a.cmp(&b))
+    }
+}
+
+/// Lexicographic order, i.e. the variable sets compared as integers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Lex;
+
+impl MonomialOrder for Lex {
+    fn cmp(&self, a: u128, b: u128) -> Ordering {
+        a.cmp(&b)
+    }
+}
+
+/// Higher degree first, equal degrees by reverse lexicographic order: the
+/// term lacking the smallest variable the two differ in is the larger one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DegRevLex;
+
+impl MonomialOrder for DegRevLex {
+    fn cmp(&self, a: u128, b: u128) -> Ordering {
+        a.count_ones().cmp(&b.count_ones()).then_with(|| {
+            let diff = a ^ b;
+            if diff == 0 {
+                Ordering::Equal
+            } else if a & diff & diff.wrapping_neg() != 0 {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            }
+        })
+    }
+}
+
+/// Lexicographic order with the variables ranked by the level of the gate
+/// driving their signal, so that every gate output is larger than the inputs
+/// of the gate. Under this order the gate polynomials `-out + f(inputs)` have
+/// their output as leading term. Primary inputs rank lowest, ties are broken
+/// by slot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitOrder {
+    /// Bit position of every slot in the permuted variable set.
+    position: [u8; 128],
+}
+
+impl CircuitOrder {
+    /// Ranks the variables by the levels of `gates`, based on the slots the
+    /// signals are currently bound to in `engine`. Later rebindings of a slot
+    /// are not reflected.
+    pub fn new(engine: &PolyEngine, gates: &[Gate]) -> Result<Self, CycleError> {
+        let schedule = Schedule::new(gates)?;
+        let mut ranks = [0; 128];
+        for (i, gate) in gates.iter().enumerate() {
+            if let Some(&var) = engine.reverse_mapping.get(&gate.out) {
+                ranks[var as usize] = schedule.level(i);
+            }
+        }
+        Ok(CircuitOrder::from_ranks(&ranks))
+    }
+
+    /// Orders the variables by `ranks[slot]`, ties broken by slot.
+    pub fn from_ranks(ranks: &[usize; 128]) -> Self {
+        let mut slots: Vec<usize> = (0..128).collect();
+        slots.sort_by_key(|&slot| (ranks[slot], slot));
+        let mut position = [0; 128];
+        for (i, slot) in slots.into_iter().enumerate() {
+            position[slot] = i as u8;
+        }
+        CircuitOrder { position }
+    }
+
+    fn permute(&self, vars: u128) -> u128 {
+        crate::set_bits(vars).fold(0, |acc, slot| acc | 1 << self.position[slot])
+    }
+}
+
+impl MonomialOrder for CircuitOrder {
+    fn cmp(&self, a: u128, b: u128) -> Ordering {
+        self.permute(a).cmp(&self.permute(b))
+    }
+}
+
+impl<O: MonomialOrder + ?Sized> MonomialOrder for &O {
+    fn cmp(&self, a: u128, b: u128) -> Ordering {
+        (**self).cmp(a, b)
+    }
+}
+
+/// Sorts `terms` from the largest to the smallest under `order`, equal
+/// variable sets by increasing factor.
+pub fn sort_terms<O: MonomialOrder>(terms: &mut [Monom], order: O) {
+    terms.sort_by(|a, b| order.cmp(b.var_product, a.var_product).then(a.factor.cmp(&b.factor)));
+}
+
+impl BPolynom {
+    /// All terms, largest first under `order`.
+    pub fn sorted_terms<O: MonomialOrder>(&self, order: O) -> Vec<Monom> {
+        let mut terms: Vec<Monom> = self.terms().collect();
+        sort_terms(&mut terms, order);
+        terms
+    }
+
+    /// Largest term under `order`, `None` for the zero polynomial.
+    pub fn leading_term<O: MonomialOrder>(&self, order: O) -> Option<Monom> {
+        self.terms().max_by(|a, b| order.cmp(a.var_product, b.var_product))
+    }
+
+    /// Factor of the leading term under `order`, 0 for the zero polynomial.
+    pub fn leading_coeff<O: MonomialOrder>(&self, order: O) -> i64 {
+        self.leading_term(order).map_or(0, |m| m.factor.get())
+    }
+
+    /// All terms in the canonical [`DegLex`] order.
+    pub fn canonical_terms(&self) -> Vec<Monom> {
+        self.sorted_terms(DegLex)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonical_order() {
+        let p = BPolynom::from([(1, vec![0]), (-1, vec![1]), (3, vec![]), (2, vec![0, 1]), (-2, vec![2])]);
+        let vars: Vec<u128> = p.canonical_terms().iter().map(|m| m.var_product).collect();
+        assert_eq!(vars, [0b11, 0b100, 0b10, 0b1, 0]);
+
+        let names = ["a", "b", "c"];
+        let text = "+2·b·a -2·c -1·b +1·a +3";
+        for _ in 0..8 {
+            let q: BPolynom = BPolynom::from([(3, vec![]), (-2, vec![2]), (1, vec![0]), (2, vec![1, 0]), (-1, vec![1])]);
+            assert_eq!(q.to_string(&names, " "), text);
+            assert_eq!(format!("{:?}", q), format!("{:?}", p));
+        }
+
+        let mut terms = vec![Monom::from((5, [0])), Monom::from((-5, [0])), Monom::from((1, [3]))];
+        sort_terms(&mut terms, DegLex);
+        assert_eq!(terms, [Monom::from((1, [3])), Monom::from((-5, [0])), Monom::from((5, [0]))]);
+    }
+
+    #[test]
+    fn leading_terms() {
+        // w = 3, x = 2, y = 1, z = 0
+        let p = BPolynom::from([(1, vec![3, 0]), (2, vec![2, 1]), (3, vec![3]), (4, vec![1])]);
+        let lt = |order: &dyn MonomialOrder| p.leading_term(order).unwrap().var_product;
+        assert_eq!(lt(&Lex), 0b1001);
+        assert_eq!(lt(&DegLex), 0b1001);
+        assert_eq!(lt(&DegRevLex), 0b0110);
+        assert_eq!(p.leading_coeff(DegRevLex), 2);
+        assert_eq!(DegRevLex.cmp(0b100, 0b100), Ordering::Equal);
+        assert_eq!(DegRevLex.cmp(0b1, 0b100), Ordering::Less);
+        assert_eq!(BPolynom::empty().leading_term(Lex), None);
+    }
+
+    #[test]
+    fn circuit_order() {
+        let gates = [
+            Gate::and(2, 0, "a".into(), 1, "b".into()),
+            Gate::xor(4, 2, "t".into(), 3, "c".into()),
+        ];
+        let mut en = PolyEngine::new(BPolynom::empty());
+        let [z, a, b, c, t] = [4, 0, 1, 3, 2].map(|s| en.var_for_signal(s, format!("n{}", s)).unwrap());
+        let order = CircuitOrder::new(&en, &gates).unwrap();
+
+        let p = BPolynom::from([(-1, vec![z]), (1, vec![t]), (1, vec![c]), (-2, vec![t, c])]);
+        assert_eq!(p.leading_term(&order), Some(Monom::from((-1, [z]))));
+        assert_eq!(order.cmp(1 << t, 1 << a | 1 << b | 1 << c), Ordering::Greater);
+        assert_eq!(order.cmp(1 << a, 1 << b), Ordering::Less);
+        assert_eq!(order.cmp(1 << c, 1 << b), Ordering::Greater);
+    }
+}
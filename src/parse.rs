@@ -0,0 +1,251 @@
+//! Parser for the output of `Monom::to_string` and `BPolynom::to_string`,
+//! e.g. `+4·q2·t1 -8·q3`. `*` may be used instead of `·`, coefficients of 1
+//! may be left out and whitespace is optional, so `4*q2*t1 - 8*q3 + t0`
+//! parses as well. Variable names are resolved to slots by a caller-supplied
+//! function.
+
+use std::fmt::Display;
+use std::num::NonZeroI64;
+
+use crate::{BPolynom, Monom, PolyEngine};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    UnexpectedEnd,
+    UnexpectedToken { pos: usize, token: String },
+    UnknownVariable(String),
+    SlotOutOfRange { name: String, slot: u32 },
+    CoefficientOverflow { pos: usize },
+    NotAMonomial,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::UnexpectedEnd => write!(f, "unexpected end of input"),
+            ParseError::UnexpectedToken { pos, token } => write!(f, "unexpected {:?} at position {}", token, pos),
+            ParseError::UnknownVariable(name) => write!(f, "unknown variable {:?}", name),
+            ParseError::SlotOutOfRange { name, slot } => write!(f, "variable {:?} resolved to invalid slot {}", name, slot),
+            ParseError::CoefficientOverflow { pos } => write!(f, "coefficient at position {} does not fit into i64", pos),
+            ParseError::NotAMonomial => write!(f, "expected exactly one term with a non-zero coefficient"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token<'a> {
+    Plus,
+    Minus,
+    Times,
+    Word(&'a str),
+}
+
+fn is_separator(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '+' | '-' | '*' | '·')
+}
+
+fn tokenize(s: &str) -> Vec<(usize, Token<'_>)> {
+    let mut tokens = vec![];
+    let mut chars = s.char_indices().peekable();
+    while let Some((pos, c)) = chars.next() {
+        let token = match c {
+            '+' => Token::Plus,
+            '-' => Token::Minus,
+            '*' | '·' => Token::Times,
+            c if c.is_whitespace() => continue,
+            _ => {
+                let mut end = pos + c.len_utf8();
+                while let Some(&(i, c)) = chars.peek() {
+                    if is_separator(c) {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                Token::Word(&s[pos..end])
+            }
+        };
+        tokens.push((pos, token));
+    }
+    tokens
+}
+
+/// Parses a sum of terms into `(coefficient, variables)` pairs. Terms are not
+/// merged and may have a coefficient of 0.
+fn parse_terms<F>(s: &str, mut resolve: F) -> Result<Vec<(i64, u128)>, ParseError>
+where
+    F: FnMut(&str) -> Option<u32>,
+{
+    let tokens = tokenize(s);
+    let unexpected = |pos: usize, token: &Token| ParseError::UnexpectedToken {
+        pos,
+        token: match token {
+            Token::Plus => "+".into(),
+            Token::Minus => "-".into(),
+            Token::Times => "*".into(),
+            Token::Word(w) => w.to_string(),
+        },
+    };
+
+    let mut terms = vec![];
+    let mut iter = tokens.iter().peekable();
+    while iter.peek().is_some() {
+        // i128 so that the magnitude of i64::MIN can be read before the sign
+        // is applied
+        let mut factor: i128 = 1;
+        if let Some((_, sign @ (Token::Plus | Token::Minus))) = iter.peek() {
+            if *sign == Token::Minus {
+                factor = -1;
+            }
+            iter.next();
+        }
+        let mut vars = 0_u128;
+        loop {
+            match iter.next() {
+                Some((pos, Token::Word(w))) if w.chars().all(|c| c.is_ascii_digit()) => {
+                    factor = w
+                        .parse::<u64>()
+                        .ok()
+                        .and_then(|n| factor.checked_mul(n as i128))
+                        .filter(|f| i64::try_from(*f).is_ok())
+                        .ok_or(ParseError::CoefficientOverflow { pos: *pos })?;
+                }
+                Some((_, Token::Word(w))) => {
+                    let slot = resolve(w).ok_or_else(|| ParseError::UnknownVariable(w.to_string()))?;
+                    if slot >= 128 {
+                        return Err(ParseError::SlotOutOfRange { name: w.to_string(), slot });
+                    }
+                    vars |= 1 << slot;
+                }
+                Some((pos, token)) => return Err(unexpected(*pos, token)),
+                None => return Err(ParseError::UnexpectedEnd),
+            }
+            match iter.peek() {
+                Some((_, Token::Times)) => {
+                    iter.next();
+                }
+                Some((_, Token::Plus | Token::Minus)) | None => break,
+                Some((pos, token)) => return Err(unexpected(*pos, token)),
+            }
+        }
+        terms.push((factor as i64, vars));
+    }
+    Ok(terms)
+}
+
+impl Monom {
+    /// Parses a single term such as `-8·q3` or `2*a*b`.
+    pub fn parse<F>(s: &str, resolve: F) -> Result<Monom, ParseError>
+    where
+        F: FnMut(&str) -> Option<u32>,
+    {
+        match parse_terms(s, resolve)?[..] {
+            [(factor, var_product)] => {
+                let factor = NonZeroI64::new(factor).ok_or(ParseError::NotAMonomial)?;
+                Ok(Monom { var_product, factor })
+            }
+            _ => Err(ParseError::NotAMonomial),
+        }
+    }
+}
+
+impl BPolynom {
+    /// Parses a sum of terms such as `+4·q2·t1 -8·q3`. Repeated terms are
+    /// added up and a lone `0` gives the empty polynomial.
+    pub fn parse<F>(s: &str, resolve: F) -> Result<BPolynom, ParseError>
+    where
+        F: FnMut(&str) -> Option<u32>,
+    {
+        let mut p = BPolynom::empty();
+        for (factor, var_product) in parse_terms(s, resolve)? {
+            if let Some(factor) = NonZeroI64::new(factor) {
+                p += &Monom { var_product, factor };
+            }
+        }
+        Ok(p)
+    }
+}
+
+impl PolyEngine {
+    /// Parses a polynomial over the variables currently known to the engine.
+    pub fn parse_poly(&self, s: &str) -> Result<BPolynom, ParseError> {
+        BPolynom::parse(s, |name| {
+            (0..128).find(|&v| !self.free_var_slots[v] && self.var_names[v] == name).map(|v| v as u32)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VAR_NAMES: [&str; 8] = ["q0", "q1", "q2", "q3", "t0", "t1", "t2", "t3"];
+
+    fn resolve(name: &str) -> Option<u32> {
+        VAR_NAMES.iter().position(|&n| n == name).map(|v| v as u32)
+    }
+
+    fn terms(p: &BPolynom) -> Vec<(u128, i64)> {
+        let mut t: Vec<_> = p.terms().map(|m| (m.var_product, m.factor.get())).collect();
+        t.sort();
+        t
+    }
+
+    #[test]
+    fn parse_to_string_output() {
+        let p = BPolynom::from([(4, vec![2, 5]), (-8, vec![3]), (1, vec![0]), (-3, vec![])]);
+        let text = p.to_string(&VAR_NAMES, " ");
+        assert_eq!(terms(&BPolynom::parse(&text, resolve).unwrap()), terms(&p));
+        let text = p.to_string(&VAR_NAMES, "\n");
+        assert_eq!(terms(&BPolynom::parse(&text, resolve).unwrap()), terms(&p));
+
+        let m = Monom::parse("+4·q2·t1", resolve).unwrap();
+        assert_eq!(m.to_string(&VAR_NAMES), "+4·t1·q2");
+
+        let p = BPolynom::from([(i64::MIN, vec![1]), (i64::MAX, vec![])]);
+        let text = p.to_string(&VAR_NAMES, " ");
+        assert!(text.contains("-9223372036854775808"));
+        assert_eq!(terms(&BPolynom::parse(&text, resolve).unwrap()), terms(&p));
+    }
+
+    #[test]
+    fn parse_ascii_variants() {
+        let p = BPolynom::parse("4*q2*t1 - 8*q3 + q0 -3", resolve).unwrap();
+        let expected = BPolynom::from([(4, vec![2, 5]), (-8, vec![3]), (1, vec![0]), (-3, vec![])]);
+        assert_eq!(terms(&p), terms(&expected));
+
+        let p = BPolynom::parse("q0*2*3 - q0 - 5 * q0", resolve).unwrap();
+        assert!(p.poly.is_empty());
+        assert!(BPolynom::parse("0", resolve).unwrap().poly.is_empty());
+        assert!(BPolynom::parse("", resolve).unwrap().poly.is_empty());
+        assert_eq!(terms(&BPolynom::parse("-q1·q1", resolve).unwrap()), vec![(2, -1)]);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(BPolynom::parse("2*x", resolve).unwrap_err(), ParseError::UnknownVariable("x".into()));
+        assert_eq!(BPolynom::parse("2*", resolve).unwrap_err(), ParseError::UnexpectedEnd);
+        assert_eq!(
+            BPolynom::parse("q0 q1", resolve).unwrap_err(),
+            ParseError::UnexpectedToken { pos: 3, token: "q1".into() }
+        );
+        assert_eq!(BPolynom::parse("q0 * + q1", resolve).unwrap_err(), ParseError::UnexpectedToken { pos: 5, token: "+".into() });
+        assert!(matches!(BPolynom::parse("99999999999999999999", resolve), Err(ParseError::CoefficientOverflow { .. })));
+        assert!(matches!(BPolynom::parse("9223372036854775808", resolve), Err(ParseError::CoefficientOverflow { .. })));
+        assert!(matches!(BPolynom::parse("-9223372036854775808 * 2", resolve), Err(ParseError::CoefficientOverflow { .. })));
+        assert_eq!(BPolynom::parse("a", |_| Some(200)).unwrap_err(), ParseError::SlotOutOfRange { name: "a".into(), slot: 200 });
+        assert_eq!(Monom::parse("q0 + q1", resolve).unwrap_err(), ParseError::NotAMonomial);
+        assert_eq!(Monom::parse("0*q0", resolve).unwrap_err(), ParseError::NotAMonomial);
+    }
+
+    #[test]
+    fn engine_parse_poly() {
+        let mut en = PolyEngine::new(BPolynom::empty());
+        let z = en.get_unsigned_poly(vec![207, 208], vec!["S0".into(), "S1".into()]);
+        let p = en.parse_poly("+2·S1 +1·S0").unwrap();
+        assert_eq!(terms(&p), terms(&z));
+        assert_eq!(en.parse_poly("S2").unwrap_err(), ParseError::UnknownVariable("S2".into()));
+    }
+}
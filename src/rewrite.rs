@@ -0,0 +1,540 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use crate::{BPolynom, Budget, EngineError, PolyEngine};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GateKind {
+    Const0,
+    Const1,
+    Not,
+    And,
+    Or,
+    Xor,
+}
+
+impl GateKind {
+    /// Number of monomials a single occurrence of the output variable turns
+    /// into, minus the one that gets replaced.
+    pub fn growth(&self) -> i64 {
+        match self {
+            GateKind::Const0 => -1,
+            GateKind::Const1 => 0,
+            GateKind::Not => 1,
+            GateKind::And => 0,
+            GateKind::Or => 2,
+            GateKind::Xor => 2,
+        }
+    }
+
+    /// Gate polynomial over the input variables as `(factor, variables)`
+    /// pairs.
+    pub fn terms(&self, inputs: &[u32]) -> Vec<(i64, u128)> {
+        let x = |i: usize| 1_u128 << inputs[i];
+        match self {
+            GateKind::Const0 => vec![],
+            GateKind::Const1 => vec![(1, 0)],
+            GateKind::Not => vec![(1, 0), (-1, x(0))],
+            GateKind::And => vec![(1, x(0) | x(1))],
+            GateKind::Or => vec![(1, x(0)), (1, x(1)), (-1, x(0) | x(1))],
+            GateKind::Xor => vec![(1, x(0)), (1, x(1)), (-2, x(0) | x(1))],
+        }
+    }
+
+    /// Output of the gate for bit-parallel input values.
+    pub fn eval_lanes(&self, inputs: &[u128]) -> u128 {
+        match self {
+            GateKind::Const0 => 0,
+            GateKind::Const1 => u128::MAX,
+            GateKind::Not => !inputs[0],
+            GateKind::And => inputs[0] & inputs[1],
+            GateKind::Or => inputs[0] | inputs[1],
+            GateKind::Xor => inputs[0] ^ inputs[1],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gate {
+    pub kind: GateKind,
+    pub out: usize,
+    pub inputs: Vec<(usize, String)>,
+}
+
+impl Gate {
+    pub fn const_0(out: usize) -> Self {
+        Gate { kind: GateKind::Const0, out, inputs: vec![] }
+    }
+
+    pub fn const_1(out: usize) -> Self {
+        Gate { kind: GateKind::Const1, out, inputs: vec![] }
+    }
+
+    pub fn not(out: usize, in1: usize, in_name: String) -> Self {
+        Gate { kind: GateKind::Not, out, inputs: vec![(in1, in_name)] }
+    }
+
+    pub fn and(out: usize, in1: usize, in_name1: String, in2: usize, in_name2: String) -> Self {
+        Gate { kind: GateKind::And, out, inputs: vec![(in1, in_name1), (in2, in_name2)] }
+    }
+
+    pub fn or(out: usize, in1: usize, in_name1: String, in2: usize, in_name2: String) -> Self {
+        Gate { kind: GateKind::Or, out, inputs: vec![(in1, in_name1), (in2, in_name2)] }
+    }
+
+    pub fn xor(out: usize, in1: usize, in_name1: String, in2: usize, in_name2: String) -> Self {
+        Gate { kind: GateKind::Xor, out, inputs: vec![(in1, in_name1), (in2, in_name2)] }
+    }
+}
+
+impl PolyEngine {
+    /// Substitutes the output variable of `gate` by its gate polynomial.
+    pub fn apply_gate(&mut self, gate: &Gate) -> Result<(), EngineError> {
+        let input = |i: usize| gate.inputs[i].0;
+        let name = |i: usize| gate.inputs[i].1.clone();
+        match gate.kind {
+            GateKind::Const0 => self.const_0_replace(gate.out),
+            GateKind::Const1 => self.const_1_replace(gate.out),
+            GateKind::Not => self.not_replace(gate.out, input(0), name(0)),
+            GateKind::And => self.and_replace(gate.out, input(0), name(0), input(1), name(1)),
+            GateKind::Or => self.or_replace(gate.out, input(0), name(0), input(1), name(1)),
+            GateKind::Xor => self.xor_replace(gate.out, input(0), name(0), input(1), name(1)),
+        }
+    }
+
+    /// Estimated change in the number of monomials of `p` if `gate` was
+    /// substituted next.
+    pub fn estimate_growth(&self, gate: &Gate) -> i64 {
+        match self.reverse_mapping.get(&gate.out) {
+            Some(&var) => self.var_occurences.len(var as usize) as i64 * gate.kind.growth(),
+            None => 0,
+        }
+    }
+}
+
+/// The gate list contains a combinational cycle through `gate`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CycleError {
+    pub gate: usize,
+}
+
+impl Display for CycleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "gate {} is part of a combinational cycle", self.gate)
+    }
+}
+
+impl std::error::Error for CycleError {}
+
+/// Tracks which gates may be substituted next. A gate is ready once every
+/// gate reading its output has been substituted, so that its output variable
+/// can be eliminated from the polynomial for good.
+#[derive(Debug, Clone)]
+pub struct Schedule<'a> {
+    gates: &'a [Gate],
+    drivers: HashMap<usize, usize>,
+    levels: Vec<usize>,
+    pending: Vec<usize>,
+    done: Vec<bool>,
+    ready: Vec<usize>,
+}
+
+impl<'a> Schedule<'a> {
+    pub fn new(gates: &'a [Gate]) -> Result<Self, CycleError> {
+        let drivers: HashMap<usize, usize> = gates.iter().enumerate().map(|(i, g)| (g.out, i)).collect();
+        let mut pending = vec![0; gates.len()];
+        let mut readers: Vec<Vec<usize>> = vec![vec![]; gates.len()];
+        for (i, g) in gates.iter().enumerate() {
+            for (input, _) in &g.inputs {
+                if let Some(&d) = drivers.get(input) {
+                    pending[d] += 1;
+                    readers[d].push(i);
+                }
+            }
+        }
+
+        // longest path from the primary inputs, computed in topological order
+        let mut levels = vec![0; gates.len()];
+        let mut unresolved: Vec<usize> = gates
+            .iter()
+            .map(|g| g.inputs.iter().filter(|(s, _)| drivers.contains_key(s)).count())
+            .collect();
+        let mut stack: Vec<usize> = (0..gates.len()).filter(|&i| unresolved[i] == 0).collect();
+        let mut visited = 0;
+        while let Some(g) = stack.pop() {
+            visited += 1;
+            levels[g] += 1;
+            for &r in &readers[g] {
+                levels[r] = levels[r].max(levels[g]);
+                unresolved[r] -= 1;
+                if unresolved[r] == 0 {
+                    stack.push(r);
+                }
+            }
+        }
+        if visited < gates.len() {
+            // every unresolved gate reads an unresolved one, so walking back
+            // as many steps as there are gates ends up on a cycle
+            let mut gate = (0..gates.len()).find(|&i| unresolved[i] > 0).unwrap();
+            for _ in 0..gates.len() {
+                gate = gates[gate]
+                    .inputs
+                    .iter()
+                    .filter_map(|(s, _)| drivers.get(s).copied())
+                    .find(|&d| unresolved[d] > 0)
+                    .unwrap();
+            }
+            return Err(CycleError { gate });
+        }
+
+        let ready = (0..gates.len()).filter(|&i| pending[i] == 0).collect();
+        Ok(Schedule { gates, drivers, levels, pending, done: vec![false; gates.len()], ready })
+    }
+
+    pub fn gates(&self) -> &'a [Gate] {
+        self.gates
+    }
+
+    /// Indices of all gates that can be substituted next, in ascending order.
+    pub fn ready(&self) -> &[usize] {
+        &self.ready
+    }
+
+    /// Distance of a gate from the primary inputs, starting at 1.
+    pub fn level(&self, gate: usize) -> usize {
+        self.levels[gate]
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.ready.is_empty()
+    }
+
+    /// Marks `gate` as substituted and releases the gates driving its inputs.
+    pub fn complete(&mut self, gate: usize) {
+        assert!(!self.done[gate], "gate {} was already substituted", gate);
+        self.done[gate] = true;
+        let pos = self.ready.binary_search(&gate).expect("gate is not ready");
+        self.ready.remove(pos);
+        for (input, _) in &self.gates[gate].inputs {
+            if let Some(&d) = self.drivers.get(input) {
+                self.pending[d] -= 1;
+                if self.pending[d] == 0 {
+                    let pos = self.ready.binary_search(&d).unwrap_err();
+                    self.ready.insert(pos, d);
+                }
+            }
+        }
+    }
+
+    /// Substitutes `gate` in `engine`, skipping gates whose output never made
+    /// it into the polynomial. The gate stays ready if the engine fails.
+    pub fn apply(&mut self, engine: &mut PolyEngine, gate: usize) -> Result<(), EngineError> {
+        if engine.reverse_mapping.contains_key(&self.gates[gate].out) {
+            engine.apply_gate(&self.gates[gate])?;
+        }
+        self.complete(gate);
+        Ok(())
+    }
+}
+
+/// Strategy picking the next gate to substitute among the ready ones.
+pub trait GateOrder {
+    /// Returns the index of the chosen gate, which must be one of
+    /// `schedule.ready()`.
+    fn select(&mut self, engine: &PolyEngine, schedule: &Schedule) -> usize;
+}
+
+/// Reverse order of the gate list, i.e. a fixed reverse-topological order if
+/// the gates are listed topologically.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FixedOrder;
+
+impl GateOrder for FixedOrder {
+    fn select(&mut self, _engine: &PolyEngine, schedule: &Schedule) -> usize {
+        *schedule.ready().last().unwrap()
+    }
+}
+
+/// Picks the gate whose substitution is estimated to grow `p` the least,
+/// based on the occurrences of its output variable and the gate type.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MinOccurrence;
+
+impl GateOrder for MinOccurrence {
+    fn select(&mut self, engine: &PolyEngine, schedule: &Schedule) -> usize {
+        *schedule
+            .ready()
+            .iter()
+            .rev()
+            .min_by_key(|&&g| engine.estimate_growth(&schedule.gates()[g]))
+            .unwrap()
+    }
+}
+
+/// Picks the ready gate farthest away from the primary inputs.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LevelBased;
+
+impl GateOrder for LevelBased {
+    fn select(&mut self, _engine: &PolyEngine, schedule: &Schedule) -> usize {
+        *schedule.ready().iter().max_by_key(|&&g| schedule.level(g)).unwrap()
+    }
+}
+
+/// Tries every ready gate on a copy of the engine, continues greedily with
+/// [`MinOccurrence`] for `depth - 1` further gates and picks the candidate
+/// leading to the smallest polynomial.
+#[derive(Debug, Clone, Copy)]
+pub struct LookAhead {
+    pub depth: usize,
+}
+
+impl GateOrder for LookAhead {
+    fn select(&mut self, engine: &PolyEngine, schedule: &Schedule) -> usize {
+        let ready = schedule.ready();
+        if ready.len() == 1 {
+            return ready[0];
+        }
+        *ready
+            .iter()
+            .rev()
+            .min_by_key(|&&candidate| {
+                let mut engine = engine.scratch_copy();
+                let mut schedule = schedule.clone();
+                let mut simulated = schedule.apply(&mut engine, candidate);
+                for _ in 1..self.depth {
+                    if simulated.is_err() || schedule.is_finished() {
+                        break;
+                    }
+                    let next = MinOccurrence.select(&engine, &schedule);
+                    simulated = schedule.apply(&mut engine, next);
+                }
+                engine.p.poly.len()
+            })
+            .unwrap()
+    }
+}
+
+/// Bounds on the size of `p` checked after every substitution. `None`
+/// disables the respective check. A single gate is substituted as a whole,
+/// so `p` may grow past the limits while it is in progress.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    pub max_monomials: Option<usize>,
+    pub max_degree: Option<u32>,
+    pub max_coeff_bits: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RewriteStats {
+    /// Gate indices in the order they were substituted.
+    pub order: Vec<usize>,
+    /// Largest sizes of `p` at the start and after each substitution.
+    pub peak_monomials: usize,
+    pub peak_degree: u32,
+    pub peak_coeff_bits: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AbortReason {
+    TooManyMonomials(usize),
+    DegreeTooHigh(u32),
+    CoefficientTooLarge(u32),
+    /// The gate list is cyclic, nothing was substituted.
+    CombinationalCycle,
+    Engine(EngineError),
+}
+
+/// Returned when a rewriting run is stopped early. The engine is left in the
+/// state right after substituting `gate`, or right before it if the run was
+/// cancelled or ran out of time.
+#[derive(Debug, Clone)]
+pub struct RewriteAbort {
+    pub reason: AbortReason,
+    pub gate: usize,
+    pub partial: BPolynom,
+    pub stats: RewriteStats,
+}
+
+/// Drives the substitution of a whole gate list into a [`PolyEngine`].
+pub struct Rewriter {
+    pub order: Box<dyn GateOrder>,
+    pub limits: Limits,
+    /// Polled before every gate, in addition to the engine's own budget.
+    pub budget: Budget,
+}
+
+impl Rewriter {
+    pub fn new<O: GateOrder + 'static>(order: O) -> Self {
+        Rewriter { order: Box::new(order), limits: Limits::default(), budget: Budget::unlimited() }
+    }
+
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn with_budget(mut self, budget: Budget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    pub fn run(&mut self, engine: &mut PolyEngine, gates: &[Gate]) -> Result<RewriteStats, RewriteAbort> {
+        let mut stats = RewriteStats {
+            peak_monomials: engine.p.poly.len(),
+            peak_degree: engine.p.degree(),
+            peak_coeff_bits: engine.p.max_coeff_bits(),
+            ..Default::default()
+        };
+        let mut schedule = match Schedule::new(gates) {
+            Ok(schedule) => schedule,
+            Err(e) => {
+                let reason = AbortReason::CombinationalCycle;
+                return Err(RewriteAbort { reason, gate: e.gate, partial: engine.p.clone(), stats });
+            }
+        };
+        while !schedule.is_finished() {
+            let gate = self.order.select(engine, &schedule);
+            if let Err(e) = self.budget.check().and_then(|_| schedule.apply(engine, gate)) {
+                return Err(RewriteAbort { reason: AbortReason::Engine(e), gate, partial: engine.p.clone(), stats });
+            }
+            stats.order.push(gate);
+            if let Some(reason) = self.update_stats(&engine.p, &mut stats) {
+                return Err(RewriteAbort { reason, gate, partial: engine.p.clone(), stats });
+            }
+        }
+        Ok(stats)
+    }
+
+    fn update_stats(&self, p: &BPolynom, stats: &mut RewriteStats) -> Option<AbortReason> {
+        let (monomials, degree, bits) = (p.poly.len(), p.degree(), p.max_coeff_bits());
+        stats.peak_monomials = stats.peak_monomials.max(monomials);
+        stats.peak_degree = stats.peak_degree.max(degree);
+        stats.peak_coeff_bits = stats.peak_coeff_bits.max(bits);
+        if self.limits.max_monomials.is_some_and(|max| monomials > max) {
+            Some(AbortReason::TooManyMonomials(monomials))
+        } else if self.limits.max_degree.is_some_and(|max| degree > max) {
+            Some(AbortReason::DegreeTooHigh(degree))
+        } else if self.limits.max_coeff_bits.is_some_and(|max| bits > max) {
+            Some(AbortReason::CoefficientTooLarge(bits))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::{multiplier, multiplier_engine};
+    use crate::CancelToken;
+    use std::time::Duration;
+
+    fn verify(width: usize, order: impl GateOrder + 'static) -> RewriteStats {
+        let m = multiplier(width);
+        let mut en = multiplier_engine(&m);
+        let stats = Rewriter::new(order).run(&mut en, &m.gates).unwrap();
+        assert!(en.p.poly.is_empty(), "remainder {}", en.p.to_string(&en.var_names, " "));
+        assert_eq!(stats.order.len(), m.gates.len());
+        stats
+    }
+
+    #[test]
+    fn schedule_levels() {
+        let m = multiplier(2);
+        let schedule = Schedule::new(&m.gates).unwrap();
+        assert!((0..4).all(|g| schedule.level(g) == 1));
+        assert!((4..m.gates.len()).all(|g| schedule.level(g) > 1));
+        assert!(schedule.ready().iter().all(|&g| m.z.contains(&m.gates[g].out)));
+
+        // cycle 2 -> 3 -> 4 -> 2 feeding signal 1
+        let gates = [
+            Gate::and(1, 0, "x0".into(), 4, "t4".into()),
+            Gate::not(2, 4, "t4".into()),
+            Gate::not(3, 2, "t2".into()),
+            Gate::not(4, 3, "t3".into()),
+        ];
+        assert!(matches!(Schedule::new(&gates), Err(CycleError { gate: 1..=3 })));
+        let mut en = multiplier_engine(&m);
+        let abort = Rewriter::new(FixedOrder).run(&mut en, &gates).unwrap_err();
+        assert_eq!(abort.reason, AbortReason::CombinationalCycle);
+        assert_eq!(en.p, multiplier_engine(&m).p);
+    }
+
+    #[test]
+    fn fixed_order_multiplier() {
+        verify(3, FixedOrder);
+    }
+
+    #[test]
+    fn min_occurrence_multiplier() {
+        verify(3, MinOccurrence);
+    }
+
+    #[test]
+    fn level_based_multiplier() {
+        verify(3, LevelBased);
+    }
+
+    #[test]
+    fn look_ahead_multiplier() {
+        let fixed = verify(2, FixedOrder);
+        let look_ahead = verify(2, LookAhead { depth: 2 });
+        assert!(look_ahead.peak_monomials <= fixed.peak_monomials);
+
+        // simulated substitutions stay out of the undo log
+        let m = multiplier(2);
+        let mut en = multiplier_engine(&m);
+        let id = en.checkpoint();
+        Rewriter::new(LookAhead { depth: 2 }).run(&mut en, &m.gates).unwrap();
+        assert!(en.p.is_empty());
+        let scratch = en.scratch_copy();
+        assert!(scratch.undo_log.is_empty() && scratch.checkpoints.is_empty());
+        en.rollback(id);
+        assert_eq!(en.p, multiplier_engine(&m).p);
+    }
+
+    #[test]
+    fn abort_on_monomial_limit() {
+        let m = multiplier(3);
+        let mut en = multiplier_engine(&m);
+        let limits = Limits { max_monomials: Some(16), ..Default::default() };
+        let abort = Rewriter::new(FixedOrder).with_limits(limits).run(&mut en, &m.gates).unwrap_err();
+        assert!(matches!(abort.reason, AbortReason::TooManyMonomials(n) if n > 16));
+        assert!(abort.stats.peak_degree >= 2 && abort.stats.peak_coeff_bits > 0);
+        assert_eq!(abort.stats.order.last(), Some(&abort.gate));
+        assert_eq!(abort.partial.poly.len(), en.p.poly.len());
+        assert!(abort.stats.peak_monomials > 16);
+    }
+
+    #[test]
+    fn abort_on_degree_limit() {
+        let m = multiplier(3);
+        let mut en = multiplier_engine(&m);
+        let limits = Limits { max_degree: Some(2), max_coeff_bits: Some(16), ..Default::default() };
+        let abort = Rewriter::new(FixedOrder).with_limits(limits).run(&mut en, &m.gates).unwrap_err();
+        assert_eq!(abort.reason, AbortReason::DegreeTooHigh(abort.stats.peak_degree));
+        assert!(abort.stats.peak_coeff_bits > 0);
+    }
+
+    #[test]
+    fn interrupt_on_cancel_and_deadline() {
+        let m = multiplier(2);
+        let mut en = multiplier_engine(&m);
+        let token = CancelToken::new();
+        token.cancel();
+        let budget = Budget::unlimited().with_cancel_token(token);
+        let abort = Rewriter::new(FixedOrder).with_budget(budget).run(&mut en, &m.gates).unwrap_err();
+        assert_eq!(abort.reason, AbortReason::Engine(EngineError::Cancelled));
+        assert!(abort.stats.order.is_empty());
+
+        en.budget = Budget::unlimited().with_timeout(Duration::ZERO);
+        let abort = Rewriter::new(MinOccurrence).run(&mut en, &m.gates).unwrap_err();
+        assert_eq!(abort.reason, AbortReason::Engine(EngineError::DeadlineExceeded));
+        assert_eq!(en.p.poly.len(), abort.partial.poly.len());
+
+        en.budget = Budget::unlimited();
+        Rewriter::new(MinOccurrence).run(&mut en, &m.gates).unwrap();
+        assert!(en.p.poly.is_empty());
+    }
+}
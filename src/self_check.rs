@@ -0,0 +1,99 @@
+use std::fmt::Display;
+
+use crate::rewrite::GateKind;
+use crate::{BPolynom, EngineError, PolyEngine};
+
+/// Input vector on which a replacement changed the value of `p`, reported
+/// when [`PolyEngine::self_check`] is enabled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelfCheckFailure {
+    /// Output signal of the substituted gate.
+    pub signal: usize,
+    /// Slot assignment before the replacement, with the output variable set
+    /// according to the gate.
+    pub assignment: u128,
+    pub before: i128,
+    pub after: i128,
+}
+
+impl Display for SelfCheckFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "replacing signal {} changed the polynomial from {} to {} for assignment {:x}",
+            self.signal, self.before, self.after, self.assignment
+        )
+    }
+}
+
+impl PolyEngine {
+    /// Compares `before`, the polynomial prior to replacing the output `var`
+    /// of a `kind` gate, with the current `p` on 128 random input vectors.
+    /// The output variable is set from the gate inputs in every vector.
+    pub(crate) fn check_replacement(
+        &mut self,
+        before: &BPolynom,
+        signal: usize,
+        var: u32,
+        kind: GateKind,
+        inputs: &[u32],
+    ) -> Result<(), EngineError> {
+        let mut lanes = [0; 128];
+        for lane in lanes.iter_mut() {
+            *lane = self.rng.next_u128();
+        }
+        let input_lanes: Vec<u128> = inputs.iter().map(|&v| lanes[v as usize]).collect();
+        // an input may have taken over the slot of the output
+        let mut before_lanes = lanes;
+        before_lanes[var as usize] = kind.eval_lanes(&input_lanes);
+
+        let expected = before.eval_lanes(&before_lanes);
+        let actual = self.p.eval_lanes(&lanes);
+        match (0..128).find(|&lane| expected[lane] != actual[lane]) {
+            Some(lane) => Err(EngineError::SelfCheckFailed(Box::new(SelfCheckFailure {
+                signal,
+                assignment: (0..128).filter(|&v| before_lanes[v] >> lane & 1 == 1).fold(0, |acc, v| acc | 1 << v),
+                before: expected[lane],
+                after: actual[lane],
+            }))),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generate::{multiplier, multiplier_engine};
+    use crate::rewrite::{GateKind, MinOccurrence, Rewriter};
+    use crate::EngineError;
+
+    #[test]
+    fn self_check_passes_on_multipliers() {
+        for width in 2..=4 {
+            let m = multiplier(width);
+            let mut en = multiplier_engine(&m);
+            en.self_check = true;
+            Rewriter::new(MinOccurrence).run(&mut en, &m.gates).unwrap();
+            assert!(en.p.is_empty());
+        }
+    }
+
+    #[test]
+    fn self_check_catches_wrong_replacement() {
+        let m = multiplier(2);
+        let mut en = multiplier_engine(&m);
+        en.self_check = true;
+        let xor = m.gates.iter().rev().find(|g| g.kind == GateKind::Xor && en.reverse_mapping.contains_key(&g.out)).unwrap();
+        let var = en.reverse_mapping[&xor.out];
+
+        // a stale occurrence set leaves one term with the output unreplaced
+        let missed = en.var_occurences.iter(var as usize).next().unwrap();
+        en.var_occurences.remove(var as usize, missed);
+        let err = en.xor_replace(xor.out, xor.inputs[0].0, xor.inputs[0].1.clone(), xor.inputs[1].0, xor.inputs[1].1.clone()).unwrap_err();
+        let EngineError::SelfCheckFailed(failure) = err else { panic!("{:?}", err) };
+        assert_eq!(failure.signal, xor.out);
+        let rest = missed & !(1 << var);
+        assert_eq!(failure.assignment & rest, rest);
+        assert_ne!(failure.before, failure.after);
+    }
+}
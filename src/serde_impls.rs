@@ -0,0 +1,219 @@
+//! Human-readable serde representation. A monomial is written as its factor
+//! and the list of its variable slots, an engine names the variables instead:
+//!
+//! ```json
+//! {
+//!   "variables": [{ "slot": 0, "signal": 207, "name": "q0" }],
+//!   "terms": [{ "factor": -8, "vars": ["q0"] }]
+//! }
+//! ```
+//!
+//! A bare polynomial has no name table, so its variables stay slot indices;
+//! serialize the engine to keep them meaningful across engines. Terms are
+//! written in the canonical `DegLex` order. Serializing an engine fails if a
+//! variable of `p` has no name or a name is used for more than one slot.
+//! Deserializing one fails on duplicate slots, signals or terms and if the
+//! result violates the engine invariants.
+
+use std::collections::HashMap;
+use std::num::NonZeroI64;
+
+use serde::de::Error as _;
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{BPolynom, Monom, PolyEngine};
+
+#[derive(Serialize, Deserialize)]
+struct MonomRepr<V> {
+    factor: i64,
+    vars: Vec<V>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct VariableRepr {
+    slot: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    signal: Option<usize>,
+    name: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EngineRepr {
+    variables: Vec<VariableRepr>,
+    terms: Vec<MonomRepr<String>>,
+}
+
+fn to_monom<E: serde::de::Error>(factor: i64, vars: impl IntoIterator<Item = usize>) -> Result<Monom, E> {
+    let factor = NonZeroI64::new(factor).ok_or_else(|| E::custom("monomial with factor 0"))?;
+    let mut m = Monom { var_product: 0, factor };
+    for var in vars {
+        if var >= 128 {
+            return Err(E::custom(format!("variable slot {} out of range", var)));
+        }
+        m.set_var(var as u32);
+    }
+    Ok(m)
+}
+
+impl Serialize for Monom {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut vars = self.all_used_vars();
+        vars.reverse();
+        MonomRepr { factor: self.factor.get(), vars }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Monom {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = MonomRepr::<usize>::deserialize(deserializer)?;
+        to_monom(repr.factor, repr.vars)
+    }
+}
+
+impl Serialize for BPolynom {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.canonical_terms().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for BPolynom {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let terms = Vec::<Monom>::deserialize(deserializer)?;
+        let mut p = BPolynom::empty();
+        for m in &terms {
+            p += m;
+        }
+        Ok(p)
+    }
+}
+
+impl Serialize for PolyEngine {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let used = self.p.support();
+        let mut variables = vec![];
+        let mut slots_by_name = HashMap::new();
+        for slot in 0..128 {
+            if self.free_var_slots[slot] && used & (1 << slot) == 0 {
+                continue;
+            }
+            let name = self.var_names[slot].clone();
+            if name.is_empty() {
+                return Err(S::Error::custom(format!("variable slot {} has no name", slot)));
+            }
+            if slots_by_name.insert(name.clone(), slot).is_some() {
+                return Err(S::Error::custom(format!("variable name {:?} is not unique", name)));
+            }
+            let mapped = self.var_mapping[slot];
+            let signal = (self.reverse_mapping.get(&mapped) == Some(&(slot as u32))).then_some(mapped);
+            variables.push(VariableRepr { slot, signal, name });
+        }
+        let terms = self
+            .p
+            .canonical_terms()
+            .iter()
+            .map(|m| {
+                let mut vars = m.all_used_vars();
+                vars.reverse();
+                MonomRepr { factor: m.factor.get(), vars: vars.into_iter().map(|v| self.var_names[v].clone()).collect() }
+            })
+            .collect();
+        EngineRepr { variables, terms }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PolyEngine {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = EngineRepr::deserialize(deserializer)?;
+        let mut engine = PolyEngine::new(BPolynom::empty());
+        let mut slots_by_name = HashMap::new();
+        for v in repr.variables {
+            if v.slot >= 128 {
+                return Err(D::Error::custom(format!("variable slot {} out of range", v.slot)));
+            }
+            if !engine.free_var_slots[v.slot] {
+                return Err(D::Error::custom(format!("duplicate variable slot {}", v.slot)));
+            }
+            if slots_by_name.insert(v.name.clone(), v.slot).is_some() {
+                return Err(D::Error::custom(format!("variable name {:?} is not unique", v.name)));
+            }
+            match v.signal {
+                Some(signal) if engine.reverse_mapping.contains_key(&signal) => {
+                    return Err(D::Error::custom(format!("duplicate signal {}", signal)));
+                }
+                Some(signal) => engine.assign_var(v.slot, signal, v.name),
+                None => {
+                    engine.free_var_slots[v.slot] = false;
+                    engine.var_names[v.slot] = v.name;
+                }
+            }
+        }
+        let mut p = BPolynom::empty();
+        for t in repr.terms {
+            let vars = t
+                .vars
+                .iter()
+                .map(|name| slots_by_name.get(name).copied().ok_or_else(|| D::Error::custom(format!("unknown variable {:?}", name))))
+                .collect::<Result<Vec<usize>, D::Error>>()?;
+            let m = to_monom::<D::Error>(t.factor, vars)?;
+            if p.poly.insert(m.var_product, m.factor).is_some() {
+                return Err(D::Error::custom(format!("duplicate term {:?}", t.vars)));
+            }
+        }
+        engine.add_from_generates(p);
+        engine.check_invariants().map_err(D::Error::custom)?;
+        Ok(engine)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::{multiplier, multiplier_engine};
+    use crate::rewrite::{FixedOrder, Rewriter};
+
+    #[test]
+    fn monom_and_poly_json() {
+        let m = Monom::from((-8, [3, 0]));
+        assert_eq!(serde_json::to_string(&m).unwrap(), r#"{"factor":-8,"vars":[0,3]}"#);
+        let back: Monom = serde_json::from_str(r#"{"factor":-8,"vars":[0,3]}"#).unwrap();
+        assert_eq!(back, m);
+
+        let p = BPolynom::from([(2, vec![1]), (1, vec![0]), (3, vec![])]);
+        let json = serde_json::to_string(&p).unwrap();
+        assert_eq!(json, r#"[{"factor":2,"vars":[1]},{"factor":1,"vars":[0]},{"factor":3,"vars":[]}]"#);
+        let back: BPolynom = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+
+        assert!(serde_json::from_str::<Monom>(r#"{"factor":0,"vars":[]}"#).is_err());
+        assert!(serde_json::from_str::<Monom>(r#"{"factor":1,"vars":[128]}"#).is_err());
+    }
+
+    #[test]
+    fn engine_json_roundtrip() {
+        let m = multiplier(2);
+        let mut en = multiplier_engine(&m);
+        Rewriter::new(FixedOrder).run(&mut en, &m.gates[6..]).unwrap();
+
+        let json = serde_json::to_string(&en).unwrap();
+        assert!(json.contains(r#""name":"n1001""#));
+        let mut back: PolyEngine = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+        assert_eq!(back.reverse_mapping, en.reverse_mapping);
+
+        Rewriter::new(FixedOrder).run(&mut back, &m.gates).unwrap();
+        assert!(back.p.poly.is_empty());
+
+        assert!(serde_json::to_string(&PolyEngine::new(BPolynom::from([(1, [0])]))).is_err());
+        assert!(serde_json::from_str::<PolyEngine>(r#"{"variables":[],"terms":[{"factor":1,"vars":["x"]}]}"#).is_err());
+
+        let engine = |variables: &str, terms: &str| {
+            serde_json::from_str::<PolyEngine>(&format!(r#"{{"variables":[{}],"terms":[{}]}}"#, variables, terms))
+        };
+        let (var, term) = (r#"{"slot":0,"signal":7,"name":"x"}"#, r#"{"factor":1,"vars":["x"]}"#);
+        assert!(engine(var, term).is_ok());
+        assert!(engine(&format!(r#"{},{{"slot":0,"name":"y"}}"#, var), term).is_err());
+        assert!(engine(&format!(r#"{},{{"slot":1,"signal":7,"name":"y"}}"#, var), term).is_err());
+        assert!(engine(var, &format!("{},{}", term, term)).is_err());
+    }
+}
@@ -0,0 +1,258 @@
+//! Versioned text format for saving and restoring a [`PolyEngine`].
+//!
+//! ```text
+//! polyengine-snapshot 1
+//! slot <var> <free> <signal> <name>
+//! signal <signal> <var>
+//! term <factor> <var_product as hex>
+//! ```
+//!
+//! There is one `slot` line for each of the 128 variable slots. Names are
+//! written until the end of the line with `\` and newlines escaped. The
+//! occurrence sets are rebuilt on load; budget and checkpoints are not saved.
+//! A snapshot with two terms over the same variables or whose slot tables do
+//! not agree with its terms is rejected as malformed.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::num::NonZeroI64;
+use std::path::Path;
+
+use crate::{BPolynom, InvariantViolation, PolyEngine};
+
+pub const SNAPSHOT_VERSION: u32 = 1;
+const MAGIC: &str = "polyengine-snapshot";
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    UnsupportedVersion(u32),
+    Malformed { line: usize, message: String },
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::Io(e) => write!(f, "snapshot i/o error: {}", e),
+            SnapshotError::UnsupportedVersion(v) => write!(f, "unsupported snapshot version {}", v),
+            SnapshotError::Malformed { line, message } => write!(f, "malformed snapshot in line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<io::Error> for SnapshotError {
+    fn from(e: io::Error) -> Self {
+        SnapshotError::Io(e)
+    }
+}
+
+fn escape(name: &str) -> String {
+    name.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(name: &str) -> String {
+    let mut output = String::with_capacity(name.len());
+    let mut chars = name.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                output.push('\n');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                output.push('\\');
+                chars.next();
+            }
+            _ => output.push(c),
+        }
+    }
+    output
+}
+
+impl PolyEngine {
+    pub fn write_snapshot<W: Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(w, "{} {}", MAGIC, SNAPSHOT_VERSION)?;
+        for var in 0..128 {
+            writeln!(
+                w,
+                "slot {} {} {} {}",
+                var,
+                self.free_var_slots[var] as u8,
+                self.var_mapping[var],
+                escape(&self.var_names[var])
+            )?;
+        }
+        let mut signals: Vec<_> = self.reverse_mapping.iter().collect();
+        signals.sort();
+        for (signal, var) in signals {
+            writeln!(w, "signal {} {}", signal, var)?;
+        }
+        for m in self.p.canonical_terms() {
+            writeln!(w, "term {} {:x}", m.factor.get(), m.var_product)?;
+        }
+        w.flush()
+    }
+
+    pub fn read_snapshot<R: BufRead>(r: R) -> Result<PolyEngine, SnapshotError> {
+        let mut lines = r.lines().enumerate();
+        let malformed = |line: usize, message: &str| SnapshotError::Malformed { line: line + 1, message: message.into() };
+
+        let (_, header) = lines.next().ok_or_else(|| malformed(0, "empty snapshot"))?;
+        let header = header?;
+        let version = header
+            .strip_prefix(MAGIC)
+            .and_then(|v| v.trim().parse::<u32>().ok())
+            .ok_or_else(|| malformed(0, "missing snapshot header"))?;
+        if version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+
+        let mut engine = PolyEngine::new(BPolynom::empty());
+        let mut p = BPolynom::empty();
+        // line of the record for each slot and signal, to locate errors
+        let mut slot_lines = [None; 128];
+        let mut signal_lines = HashMap::new();
+        let mut last = 0;
+        for (i, line) in lines {
+            let line = line?;
+            last = i;
+            if line.is_empty() {
+                continue;
+            }
+            let mut fields = line.splitn(5, ' ');
+            let mut next = |what: &str| fields.next().ok_or_else(|| malformed(i, &format!("missing {}", what)));
+            match next("record type")? {
+                "slot" => {
+                    let var: usize = next("slot")?.parse().map_err(|_| malformed(i, "invalid slot"))?;
+                    if var >= 128 {
+                        return Err(malformed(i, "slot out of range"));
+                    }
+                    if slot_lines[var].replace(i).is_some() {
+                        return Err(malformed(i, "duplicate slot"));
+                    }
+                    engine.free_var_slots[var] = match next("free flag")? {
+                        "0" => false,
+                        "1" => true,
+                        _ => return Err(malformed(i, "invalid free flag")),
+                    };
+                    engine.var_mapping[var] = next("signal")?.parse().map_err(|_| malformed(i, "invalid signal"))?;
+                    engine.var_names[var] = unescape(next("name").unwrap_or(""));
+                }
+                "signal" => {
+                    let signal = next("signal")?.parse().map_err(|_| malformed(i, "invalid signal"))?;
+                    let var: u32 = next("slot")?.parse().map_err(|_| malformed(i, "invalid slot"))?;
+                    if var >= 128 {
+                        return Err(malformed(i, "slot out of range"));
+                    }
+                    if signal_lines.insert(signal, i).is_some() {
+                        return Err(malformed(i, "duplicate signal"));
+                    }
+                    engine.reverse_mapping.insert(signal, var);
+                }
+                "term" => {
+                    let factor: i64 = next("factor")?.parse().map_err(|_| malformed(i, "invalid factor"))?;
+                    let factor = NonZeroI64::new(factor).ok_or_else(|| malformed(i, "zero factor"))?;
+                    let var_product = u128::from_str_radix(next("variables")?, 16).map_err(|_| malformed(i, "invalid variables"))?;
+                    if p.poly.insert(var_product, factor).is_some() {
+                        return Err(malformed(i, "duplicate term"));
+                    }
+                }
+                other => return Err(malformed(i, &format!("unknown record {:?}", other))),
+            }
+        }
+        if let Some(var) = slot_lines.iter().position(Option::is_none) {
+            return Err(malformed(last, &format!("missing slot {}", var)));
+        }
+        engine.add_from_generates(p);
+        if let Err(violation) = engine.check_invariants() {
+            let line = match violation {
+                InvariantViolation::VariableInFreeSlot { var } => slot_lines[var].unwrap(),
+                InvariantViolation::MappingMismatch { signal, .. } => signal_lines[&signal],
+                _ => last,
+            };
+            return Err(malformed(line, &violation.to_string()));
+        }
+        Ok(engine)
+    }
+
+    pub fn save_snapshot<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write_snapshot(BufWriter::new(File::create(path)?))
+    }
+
+    pub fn load_snapshot<P: AsRef<Path>>(path: P) -> Result<PolyEngine, SnapshotError> {
+        PolyEngine::read_snapshot(BufReader::new(File::open(path)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::{multiplier, multiplier_engine};
+    use crate::rewrite::{FixedOrder, Rewriter};
+
+    #[test]
+    fn snapshot_roundtrip_and_resume() {
+        let m = multiplier(3);
+        let mut en = multiplier_engine(&m);
+        en.var_names[0] = String::from("odd \\ name\nwith newline");
+        Rewriter::new(FixedOrder).run(&mut en, &m.gates[20..]).unwrap();
+
+        let path = std::env::temp_dir().join(format!("polyengine-snapshot-{}.txt", std::process::id()));
+        en.save_snapshot(&path).unwrap();
+        let mut loaded = PolyEngine::load_snapshot(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let terms = |e: &PolyEngine| {
+            let mut t: Vec<(u128, i64)> = e.p.terms().map(|m| (m.var_product, m.factor.get())).collect();
+            t.sort();
+            t
+        };
+        assert_eq!(terms(&loaded), terms(&en));
+        assert_eq!(loaded.var_names, en.var_names);
+        assert_eq!(loaded.var_mapping, en.var_mapping);
+        assert_eq!(loaded.reverse_mapping, en.reverse_mapping);
+        assert_eq!(loaded.free_var_slots, en.free_var_slots);
+        assert_eq!(loaded.var_occurences, en.var_occurences);
+
+        Rewriter::new(FixedOrder).run(&mut loaded, &m.gates).unwrap();
+        assert!(loaded.p.poly.is_empty());
+    }
+
+    #[test]
+    fn snapshot_errors() {
+        let err = PolyEngine::read_snapshot("polyengine-snapshot 99\n".as_bytes()).unwrap_err();
+        assert!(matches!(err, SnapshotError::UnsupportedVersion(99)));
+        let err = PolyEngine::read_snapshot("polyengine-snapshot 1\nterm 0 3\n".as_bytes()).unwrap_err();
+        assert!(matches!(err, SnapshotError::Malformed { line: 2, .. }));
+        let err = PolyEngine::read_snapshot("".as_bytes()).unwrap_err();
+        assert!(matches!(err, SnapshotError::Malformed { line: 1, .. }));
+
+        // valid snapshot with slot 0 bound to signal 7 and the term 3·x0,
+        // `edit` replaces a line
+        let mut en = PolyEngine::new(BPolynom::empty());
+        let var = en.var_for_signal(7, "a".into()).unwrap();
+        en.add_from_generates(BPolynom::from([(3, vec![var])]));
+        let mut text = vec![];
+        en.write_snapshot(&mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(PolyEngine::read_snapshot(text.as_bytes()).is_ok());
+        let read = |edit: &dyn Fn(&str) -> String| PolyEngine::read_snapshot(edit(&text).as_bytes()).unwrap_err();
+        let line = |err: SnapshotError| match err {
+            SnapshotError::Malformed { line, .. } => line,
+            other => panic!("unexpected error {}", other),
+        };
+        assert_eq!(line(read(&|t| t.replace("slot 0 0 ", "slot 0 yes "))), 2);
+        assert_eq!(line(read(&|t| t.replace("slot 1 1 ", "slot 0 0 "))), 3);
+        assert_eq!(line(read(&|t| t.replace("slot 1 1 0 \n", ""))), 130);
+        assert_eq!(line(read(&|t| t.replace("term 3 1", "term 3 1\nterm -3 1"))), 4 + 128);
+        // term in a free slot, reported at the slot, and signal pointing at a
+        // free slot or a slot bound to another signal
+        assert_eq!(line(read(&|t| t.replace("term 3 1", "term 3 2"))), 3);
+        assert_eq!(line(read(&|t| t.replace("signal 7 0", "signal 7 1"))), 2 + 128);
+        assert_eq!(line(read(&|t| t.replace("slot 0 0 7", "slot 0 0 8"))), 2 + 128);
+    }
+}
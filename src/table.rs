@@ -0,0 +1,182 @@
+//! Conversion between polynomials and value tables over a list of variable
+//! slots. Entry `i` of a table holds the value for the assignment in which
+//! variable `vars[j]` is 1 iff bit `j` of `i` is set.
+//!
+//! The factors of the polynomial are the Möbius transform of the table and
+//! the table is the zeta transform of the factors, both computed in place in
+//! `O(n·2^n)` for `n` variables.
+
+use std::fmt::Display;
+
+use crate::BPolynom;
+
+/// Largest number of variables a table may have.
+pub const MAX_TABLE_VARS: usize = 24;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableError {
+    TooManyVariables(usize),
+    /// The table length is not `2^vars.len()`.
+    LengthMismatch { expected: usize, actual: usize },
+    /// `p` contains a variable outside of `vars`.
+    UnlistedVariable(usize),
+    /// A slot is listed twice or is not below 128.
+    InvalidVariable(u32),
+    /// A factor or value does not fit into an `i64`.
+    Overflow,
+}
+
+impl Display for TableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TableError::TooManyVariables(n) => write!(f, "{} variables exceed the limit of {}", n, MAX_TABLE_VARS),
+            TableError::LengthMismatch { expected, actual } => {
+                write!(f, "table has {} entries, expected {}", actual, expected)
+            }
+            TableError::UnlistedVariable(var) => write!(f, "variable {} is not part of the table", var),
+            TableError::InvalidVariable(var) => write!(f, "variable {} is out of range or listed twice", var),
+            TableError::Overflow => write!(f, "value does not fit into i64"),
+        }
+    }
+}
+
+impl std::error::Error for TableError {}
+
+fn check_vars(vars: &[u32]) -> Result<(), TableError> {
+    if vars.len() > MAX_TABLE_VARS {
+        return Err(TableError::TooManyVariables(vars.len()));
+    }
+    let mut seen = 0_u128;
+    for &var in vars {
+        if var >= 128 || seen >> var & 1 == 1 {
+            return Err(TableError::InvalidVariable(var));
+        }
+        seen |= 1 << var;
+    }
+    Ok(())
+}
+
+/// In-place transform over the subset lattice, adding (zeta) or
+/// subtracting (Möbius) every entry to or from the entries of its supersets.
+fn transform(values: &mut [i64], inverse: bool) -> Result<(), TableError> {
+    let mut step = 1;
+    while step < values.len() {
+        for i in 0..values.len() {
+            if i & step != 0 {
+                let sum = if inverse {
+                    values[i].checked_sub(values[i ^ step])
+                } else {
+                    values[i].checked_add(values[i ^ step])
+                };
+                values[i] = sum.ok_or(TableError::Overflow)?;
+            }
+        }
+        step <<= 1;
+    }
+    Ok(())
+}
+
+/// Values of `p` for all assignments of `vars`, which must cover every
+/// variable of `p`, without overflow checks.
+pub(crate) fn value_table(p: &BPolynom, vars: &[usize]) -> Vec<i128> {
+    let mut values = vec![0_i128; 1 << vars.len()];
+    for (&var_product, factor) in p.poly.iter() {
+        let index = vars.iter().enumerate().filter(|&(_, &v)| var_product >> v & 1 == 1).fold(0, |acc, (j, _)| acc | 1 << j);
+        values[index] += factor.get() as i128;
+    }
+    let mut step = 1;
+    while step < values.len() {
+        for i in 0..values.len() {
+            if i & step != 0 {
+                values[i] += values[i ^ step];
+            }
+        }
+        step <<= 1;
+    }
+    values
+}
+
+impl BPolynom {
+    /// Polynomial over the slots `vars` taking the values of `table`.
+    pub fn from_table(vars: &[u32], table: &[i64]) -> Result<BPolynom, TableError> {
+        check_vars(vars)?;
+        if table.len() != 1 << vars.len() {
+            return Err(TableError::LengthMismatch { expected: 1 << vars.len(), actual: table.len() });
+        }
+        let mut factors = table.to_vec();
+        transform(&mut factors, true)?;
+        let mut p = BPolynom::empty();
+        for (i, &factor) in factors.iter().enumerate() {
+            let var_product = vars.iter().enumerate().filter(|&(j, _)| i >> j & 1 == 1).fold(0, |acc, (_, &v)| acc | 1 << v);
+            p.add_term(var_product, factor);
+        }
+        Ok(p)
+    }
+
+    /// Polynomial over the slots `vars` that is 1 exactly where `table` is
+    /// true.
+    pub fn from_truth_table(vars: &[u32], table: &[bool]) -> Result<BPolynom, TableError> {
+        let table: Vec<i64> = table.iter().map(|&b| b as i64).collect();
+        BPolynom::from_table(vars, &table)
+    }
+
+    /// Values of the polynomial for all assignments of the slots `vars`,
+    /// which must cover every variable of `p`.
+    pub fn to_table(&self, vars: &[u32]) -> Result<Vec<i64>, TableError> {
+        check_vars(vars)?;
+        let mut values = vec![0; 1 << vars.len()];
+        for (&var_product, factor) in self.poly.iter() {
+            let mut index = 0;
+            let mut rest = var_product;
+            for (j, &v) in vars.iter().enumerate() {
+                if rest & 1 << v != 0 {
+                    index |= 1 << j;
+                    rest &= !(1 << v);
+                }
+            }
+            if rest != 0 {
+                return Err(TableError::UnlistedVariable(rest.trailing_zeros() as usize));
+            }
+            values[index] = factor.get();
+        }
+        transform(&mut values, false)?;
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_roundtrip() {
+        // full adder carry over slots 4, 2, 7
+        let vars = [4, 2, 7];
+        let carry: Vec<bool> = (0..8).map(|i: u32| i.count_ones() >= 2).collect();
+        let p = BPolynom::from_truth_table(&vars, &carry).unwrap();
+        assert_eq!(p, BPolynom::from([(1, vec![4, 2]), (1, vec![4, 7]), (1, vec![2, 7]), (-2, vec![4, 2, 7])]));
+        assert_eq!(p.to_table(&vars).unwrap(), carry.iter().map(|&b| b as i64).collect::<Vec<_>>());
+
+        let q = BPolynom::from([(3, vec![0, 1]), (-5, vec![2]), (7, vec![])]);
+        let table = q.to_table(&[0, 1, 2]).unwrap();
+        for (i, &v) in table.iter().enumerate() {
+            assert_eq!(v as i128, q.eval(i as u128));
+        }
+        assert_eq!(BPolynom::from_table(&[0, 1, 2], &table).unwrap(), q);
+        assert_eq!(BPolynom::from_table(&[], &[0]).unwrap(), BPolynom::empty());
+    }
+
+    #[test]
+    fn table_errors() {
+        let q = BPolynom::from([(1, vec![0, 5])]);
+        assert_eq!(q.to_table(&[0, 1]).unwrap_err(), TableError::UnlistedVariable(5));
+        assert_eq!(BPolynom::from_table(&[0, 1], &[0; 3]).unwrap_err(), TableError::LengthMismatch { expected: 4, actual: 3 });
+        let vars: Vec<u32> = (0..25).collect();
+        assert_eq!(q.to_table(&vars).unwrap_err(), TableError::TooManyVariables(25));
+        assert_eq!(BPolynom::from_table(&[0], &[i64::MIN, i64::MAX]).unwrap_err(), TableError::Overflow);
+        assert_eq!(BPolynom::from_table(&[3, 3], &[0, 1, 1, 0]).unwrap_err(), TableError::InvalidVariable(3));
+        assert_eq!(q.to_table(&[0, 5, 0]).unwrap_err(), TableError::InvalidVariable(0));
+        assert_eq!(q.to_table(&[0, 200]).unwrap_err(), TableError::InvalidVariable(200));
+        assert_eq!(BPolynom::from_table(&[128], &[0, 1]).unwrap_err(), TableError::InvalidVariable(128));
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::HashSet;
+
+use std::num::NonZeroI64;
+
+use crate::PolyEngine;
+
+/// Previous value of a piece of engine state, recorded while a checkpoint is
+/// active. The occurrence sets are not logged, they are derived from `p` and
+/// rebuilt for every touched monomial on rollback.
+#[derive(Debug, Clone)]
+pub(crate) enum Undo {
+    Term(u128, Option<NonZeroI64>),
+    Slot { var: usize, name: String, mapping: usize, free: bool },
+    Reverse(usize, Option<u32>),
+}
+
+/// Handle returned by [`PolyEngine::checkpoint`]. Ids are never reused
+/// within an engine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CheckpointId(usize);
+
+impl PolyEngine {
+    /// Starts recording changes so that the current state can be restored
+    /// with [`PolyEngine::rollback`]. Checkpoints nest. Only changes made
+    /// through the engine's methods are recorded, not writes to its fields.
+    pub fn checkpoint(&mut self) -> CheckpointId {
+        let id = CheckpointId(self.next_checkpoint);
+        self.next_checkpoint += 1;
+        self.checkpoints.push((id, self.undo_log.len()));
+        id
+    }
+
+    /// Restores the state at `id` and drops it together with all checkpoints
+    /// taken after it.
+    ///
+    /// Panics if `id` was already rolled back or committed.
+    pub fn rollback(&mut self, id: CheckpointId) {
+        let start = self.take_checkpoint(id);
+        let mut touched = HashSet::new();
+        while self.undo_log.len() > start {
+            match self.undo_log.pop().unwrap() {
+                Undo::Term(vars, previous) => {
+                    match previous {
+                        Some(factor) => self.p.poly.insert(vars, factor),
+                        None => self.p.poly.remove(&vars),
+                    };
+                    touched.insert(vars);
+                }
+                Undo::Slot { var, name, mapping, free } => {
+                    self.var_names[var] = name;
+                    self.var_mapping[var] = mapping;
+                    self.free_var_slots[var] = free;
+                }
+                Undo::Reverse(signal, previous) => match previous {
+                    Some(var) => {
+                        self.reverse_mapping.insert(signal, var);
+                    }
+                    None => {
+                        self.reverse_mapping.remove(&signal);
+                    }
+                },
+            }
+        }
+        for vars in touched {
+            if self.p.poly.contains_key(&vars) {
+                self.var_occurences.insert_term(vars);
+            } else {
+                self.var_occurences.remove_term(vars);
+            }
+        }
+    }
+
+    /// Keeps all changes since `id` and drops it together with all
+    /// checkpoints taken after it.
+    ///
+    /// Panics if `id` was already rolled back or committed.
+    pub fn commit(&mut self, id: CheckpointId) {
+        self.take_checkpoint(id);
+        if self.checkpoints.is_empty() {
+            self.undo_log.clear();
+        }
+    }
+
+    fn take_checkpoint(&mut self, id: CheckpointId) -> usize {
+        let i = self
+            .checkpoints
+            .iter()
+            .position(|&(active, _)| active == id)
+            .unwrap_or_else(|| panic!("checkpoint {} is no longer active", id.0));
+        let start = self.checkpoints[i].1;
+        self.checkpoints.truncate(i);
+        start
+    }
+
+    /// Copy for trial substitutions that are thrown away afterwards, without
+    /// undo history, checkpoints or tracing.
+    pub(crate) fn scratch_copy(&self) -> PolyEngine {
+        PolyEngine {
+            p: self.p.clone(),
+            var_names: self.var_names.clone(),
+            var_mapping: self.var_mapping,
+            reverse_mapping: self.reverse_mapping.clone(),
+            var_occurences: self.var_occurences.clone(),
+            free_var_slots: self.free_var_slots,
+            budget: self.budget.clone(),
+            debug_invariants: self.debug_invariants,
+            self_check: self.self_check,
+            trace: false,
+            rng: self.rng.clone(),
+            undo_log: vec![],
+            checkpoints: vec![],
+            next_checkpoint: self.next_checkpoint,
+        }
+    }
+
+    fn recording(&self) -> bool {
+        !self.checkpoints.is_empty()
+    }
+
+    /// Records `previous` as the factor of the term with the variables
+    /// `vars` before a change.
+    pub(crate) fn save_term(&mut self, vars: u128, previous: Option<NonZeroI64>) {
+        if self.recording() {
+            self.undo_log.push(Undo::Term(vars, previous));
+        }
+    }
+
+    pub(crate) fn save_slot(&mut self, var: usize) {
+        if self.recording() {
+            self.undo_log.push(Undo::Slot {
+                var,
+                name: self.var_names[var].clone(),
+                mapping: self.var_mapping[var],
+                free: self.free_var_slots[var],
+            });
+        }
+    }
+
+    pub(crate) fn save_reverse(&mut self, signal: usize) {
+        if self.recording() {
+            let previous = self.reverse_mapping.get(&signal).copied();
+            self.undo_log.push(Undo::Reverse(signal, previous));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generate::{multiplier, multiplier_engine};
+    use crate::rewrite::{FixedOrder, Rewriter};
+
+    #[test]
+    fn rollback_restores_state() {
+        let m = multiplier(3);
+        let mut en = multiplier_engine(&m);
+        let before = en.clone();
+        let id = en.checkpoint();
+        Rewriter::new(FixedOrder).run(&mut en, &m.gates[..12]).unwrap();
+        assert_ne!(en.p, before.p);
+        en.rollback(id);
+
+        assert_eq!(en.p, before.p);
+        assert_eq!(en.var_names, before.var_names);
+        assert_eq!(en.var_mapping, before.var_mapping);
+        assert_eq!(en.reverse_mapping, before.reverse_mapping);
+        assert_eq!(en.free_var_slots, before.free_var_slots);
+        assert_eq!(en.var_occurences, before.var_occurences);
+
+        Rewriter::new(FixedOrder).run(&mut en, &m.gates).unwrap();
+        assert!(en.p.poly.is_empty());
+    }
+
+    #[test]
+    fn nested_checkpoints() {
+        let m = multiplier(2);
+        let mut en = multiplier_engine(&m);
+        let outer = en.checkpoint();
+        Rewriter::new(FixedOrder).run(&mut en, &m.gates[4..]).unwrap();
+        let after_adders = en.p.clone();
+        let inner = en.checkpoint();
+        Rewriter::new(FixedOrder).run(&mut en, &m.gates[..4]).unwrap();
+        assert!(en.p.poly.is_empty());
+        en.rollback(inner);
+        assert_eq!(en.p, after_adders);
+        en.commit(outer);
+        assert!(en.undo_log.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "no longer active")]
+    fn stale_id_after_rollback() {
+        let mut en = multiplier_engine(&multiplier(2));
+        let a = en.checkpoint();
+        en.rollback(a);
+        let b = en.checkpoint();
+        assert_ne!(a, b);
+        en.rollback(a);
+    }
+
+    #[test]
+    #[should_panic(expected = "no longer active")]
+    fn stale_id_after_commit() {
+        let mut en = multiplier_engine(&multiplier(2));
+        let a = en.checkpoint();
+        en.commit(a);
+        let _b = en.checkpoint();
+        en.rollback(a);
+    }
+}